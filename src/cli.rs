@@ -0,0 +1,475 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use crate::derivation::DerivationStyle;
+use crate::dictionary::KaikkiFilter;
+use crate::features::{self, Registration, Stopwords};
+use crate::scaling::ScalingMethod;
+use crate::tokenizer::Normalizer;
+use crate::{SegmenterKind, SenseMode, TRAINING_SEED};
+
+pub const USAGE: &str = "\
+Usage: erebus [OPTIONS] [WORD_LIST]
+
+Segments words into morphemes and derives morpheme embeddings from their
+definitions. WORD_LIST is a newline separated file (`#` starts a comment);
+without it every dictionary headword is processed.
+
+Options:
+  --dictionary <PATH>    Load extra definitions from a TSV (word<TAB>definition),
+                         CSV (.csv), JSON (.json) or JSON Lines (.jsonl) file.
+                         May be repeated; later files win.
+  --kaikki <PATH>        Stream a Kaikki.org Wiktionary JSONL extract into the
+                         dictionary, harvesting etymology templates as gold
+                         segmentations. May be repeated.
+  --lang <CODE>          Language code kept from Kaikki extracts (default: en).
+  --pos <LIST>           Comma separated parts of speech kept from Kaikki
+                         extracts (default: all).
+  --replace-dictionary   Ignore the built-in entries and use only loaded files.
+  --morphology <PATH>    Load prefix/suffix/root tables from a TOML morphology
+                         profile instead of the built-in ones.
+  --segmenter <LIST>     Comma separated segmentation strategies: `greedy` affix
+                         peeling (default), `viterbi` optimal scoring of whole
+                         segmentations, `morfessor` unsupervised MDL
+                         segmentation, or the `bpe` and `unigram` subword
+                         baselines. The last three are learned from the word
+                         list. Several strategies run the pipeline once each
+                         and end with a side by side comparison.
+  --vocab-size <N>       Target vocabulary size of the subword baselines
+                         (default: 1000).
+  --train-corpus <PATH>  Word list used to train a learned segmenter instead of
+                         the words being processed.
+  --segmenter-model <PATH>
+                         Load a previously saved model instead of training
+                         (needs a single learned segmenter).
+  --save-segmenter-model <PATH>
+                         Write the trained (or loaded) model to PATH.
+  --compounds            Split compounds into free morphemes (dictionary
+                         headwords, optionally joined by a linking element such
+                         as `s` or `o`) before running the segmenter.
+  --derivation <STYLE>   Print each word's derivation tree, `bracketed`
+                         (`((un kind) ness)`) or as an indented `tree`, and
+                         add every intermediate derived stem to the embedding
+                         matrix as a `stem:` row.
+  --eval <GOLD>          Score the segmenters against a gold file
+                         (word<TAB>morph morph, alternatives separated by
+                         commas, Morpho Challenge style) instead of building
+                         embeddings: boundary precision/recall/F1, exact-match
+                         rate and every mistaken word.
+  --features <LIST>      Comma separated feature extractors whose vectors are
+                         concatenated into each definition's features
+                         (default: basic). Built in: `basic` word count, token
+                         length, sensory/abstract ratios and complexity; `bow`
+                         word counts and `tfidf` TF-IDF weights over a
+                         vocabulary shared by every loaded definition;
+                         `skipgram` the mean of word vectors trained on every
+                         loaded definition; `vectors` the mean of pretrained
+                         word vectors from --vectors; `readability` syllables
+                         per word, words per sentence, Flesch-Kincaid grade
+                         and Gunning fog; `lexicon` one share per --lexicon
+                         category (builtin: sensory, abstract); `pos` part-of-
+                         speech shares and the headword's part of speech.
+                         Without --vectors the default is `basic`, with it
+                         `vectors,basic`; --lexicon adds `lexicon` to the
+                         default.
+  --vectors <PATH>       Pretrained word vectors in word2vec/GloVe/fastText
+                         text format (`word v1 v2 ...`, optional
+                         `count dims` header line).
+  --vectors-idf          Weight each word vector by its inverse document
+                         frequency across the loaded definitions.
+  --normalize <LIST>     Comma separated token normalizers applied, in order,
+                         before every feature extractor: `spelling` British
+                         to American spellings, `lemma` irregular forms and
+                         regular inflections to their lemma, `stem` the
+                         Porter2 stemmer (default: none).
+  --lemmas <PATH>        Extra form<TAB>lemma pairs for `lemma`.
+  --cmudict <PATH>       CMU Pronouncing Dictionary whose syllable counts
+                         replace the built-in heuristics of `readability` and
+                         `basic`.
+  --lexicon <PATH>       Semantic lexicon for `lexicon`: a TSV of
+                         word<TAB>category[<TAB>weight] lines, or a word list
+                         whose file name is the category. May be repeated;
+                         words are matched on their lemma.
+  --stopwords <LIST>     Words ignored by `bow`, `tfidf` and `skipgram`:
+                         `builtin` English function words (default), `none`,
+                         or a word list file. Also applies to `vectors`.
+  --skipgram-dims <N>    Size of the `skipgram` word vectors (default: 32).
+  --skipgram-epochs <N>  Passes over the definitions when training `skipgram`
+                         (default: 5).
+  --seed <N>             Seed for every randomized step (morfessor, `--dims`,
+                         `skipgram`), so runs are reproducible.
+  --scale <METHOD>       Scale every feature dimension across the loaded
+                         definitions: `zscore`, `minmax`, `l2` (divide by
+                         the dimension's norm) or `rank` (percentile).
+  --scaler <PATH>        Scale with statistics saved by --save-scaler instead
+                         of fitting them on this run's definitions.
+  --save-scaler <PATH>   Write the fitted (or loaded) scaling statistics to
+                         PATH.
+  --dims <K>             Reduce the morpheme embedding matrix to K dense
+                         dimensions with a truncated (randomized) SVD, i.e.
+                         latent semantic analysis over `bow`/`tfidf` features.
+  --min-count <N>        Leave morphemes found in fewer than N words out of
+                         the embedding matrix (default: 1).
+  --stats                Print each morpheme's variance, standard error of
+                         the mean, 95% confidence interval, minimum and
+                         maximum per feature under its row.
+  --nbest <N>            Keep the N best segmentations per word (viterbi only),
+                         print them with their confidences, and weight each
+                         one's morpheme contributions by that confidence.
+  --senses <MODE>        How multiple senses feed the embeddings: `average` them
+                         into one observation per word (default) or keep them
+                         `separate`.
+  -h, --help             Print this help.
+";
+
+/// A dictionary file to merge, in command line order.
+#[derive(Debug, Clone)]
+pub enum DictionarySource {
+    File(PathBuf),
+    Kaikki(PathBuf),
+}
+
+/// Command line configuration for a single run.
+#[derive(Debug)]
+pub struct Options {
+    pub word_list: Option<PathBuf>,
+    pub dictionaries: Vec<DictionarySource>,
+    pub kaikki_filter: KaikkiFilter,
+    pub replace_dictionary: bool,
+    pub morphology: Option<PathBuf>,
+    pub segmenters: Vec<SegmenterKind>,
+    pub vocab_size: usize,
+    pub train_corpus: Option<PathBuf>,
+    pub segmenter_model: Option<PathBuf>,
+    pub save_segmenter_model: Option<PathBuf>,
+    pub compounds: bool,
+    pub derivation: Option<DerivationStyle>,
+    pub eval: Option<PathBuf>,
+    pub features: Vec<&'static Registration>,
+    pub stopwords: Stopwords,
+    pub vectors: Option<PathBuf>,
+    pub vectors_idf: bool,
+    pub lexicons: Vec<PathBuf>,
+    pub normalizers: Vec<Normalizer>,
+    pub lemmas: Option<PathBuf>,
+    pub cmudict: Option<PathBuf>,
+    pub skipgram_dims: usize,
+    pub skipgram_epochs: usize,
+    pub seed: u64,
+    pub scale: Option<ScalingMethod>,
+    pub scaler: Option<PathBuf>,
+    pub save_scaler: Option<PathBuf>,
+    pub dims: Option<usize>,
+    pub min_count: usize,
+    pub stats: bool,
+    pub nbest: usize,
+    pub sense_mode: SenseMode,
+    pub show_help: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            word_list: None,
+            dictionaries: Vec::new(),
+            kaikki_filter: KaikkiFilter::default(),
+            replace_dictionary: false,
+            morphology: None,
+            segmenters: vec![SegmenterKind::default()],
+            vocab_size: 1000,
+            train_corpus: None,
+            segmenter_model: None,
+            save_segmenter_model: None,
+            compounds: false,
+            derivation: None,
+            eval: None,
+            features: vec![&features::REGISTRY[0]],
+            stopwords: Stopwords::default(),
+            vectors: None,
+            vectors_idf: false,
+            lexicons: Vec::new(),
+            normalizers: Vec::new(),
+            lemmas: None,
+            cmudict: None,
+            skipgram_dims: 32,
+            skipgram_epochs: 5,
+            seed: TRAINING_SEED,
+            scale: None,
+            scaler: None,
+            save_scaler: None,
+            dims: None,
+            min_count: 1,
+            stats: false,
+            nbest: 1,
+            sense_mode: SenseMode::default(),
+            show_help: false,
+        }
+    }
+}
+
+impl Options {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, Box<dyn Error>> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+        let mut features_given = false;
+
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value)),
+                _ => (arg.clone(), None),
+            };
+            let mut value = |name: &str| -> Result<String, Box<dyn Error>> {
+                match inline_value {
+                    Some(value) => Ok(value.to_string()),
+                    None => args
+                        .next()
+                        .ok_or_else(|| format!("{name} expects a value").into()),
+                }
+            };
+
+            match flag.as_str() {
+                "-h" | "--help" => options.show_help = true,
+                "--dictionary" => options
+                    .dictionaries
+                    .push(DictionarySource::File(PathBuf::from(expand_tilde(&value(
+                        "--dictionary",
+                    )?)))),
+                "--kaikki" => options
+                    .dictionaries
+                    .push(DictionarySource::Kaikki(PathBuf::from(expand_tilde(
+                        &value("--kaikki")?,
+                    )))),
+                "--lang" => options.kaikki_filter.lang_code = value("--lang")?,
+                "--pos" => {
+                    options.kaikki_filter.parts_of_speech = value("--pos")?
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|pos| !pos.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                }
+                "--replace-dictionary" => options.replace_dictionary = true,
+                "--morphology" => {
+                    options.morphology = Some(PathBuf::from(expand_tilde(&value("--morphology")?)))
+                }
+                "--segmenter" => {
+                    options.segmenters.clear();
+                    for name in value("--segmenter")?.split(',').map(str::trim) {
+                        let kind: SegmenterKind = name.parse()?;
+                        if !options.segmenters.contains(&kind) {
+                            options.segmenters.push(kind);
+                        }
+                    }
+                }
+                "--vocab-size" => {
+                    options.vocab_size = value("--vocab-size")?
+                        .parse()
+                        .ok()
+                        .filter(|n| *n >= 1)
+                        .ok_or("--vocab-size expects a positive integer")?
+                }
+                "--train-corpus" => {
+                    options.train_corpus =
+                        Some(PathBuf::from(expand_tilde(&value("--train-corpus")?)))
+                }
+                "--segmenter-model" => {
+                    options.segmenter_model =
+                        Some(PathBuf::from(expand_tilde(&value("--segmenter-model")?)))
+                }
+                "--save-segmenter-model" => {
+                    options.save_segmenter_model = Some(PathBuf::from(expand_tilde(&value(
+                        "--save-segmenter-model",
+                    )?)))
+                }
+                "--compounds" => options.compounds = true,
+                "--derivation" => options.derivation = Some(value("--derivation")?.parse()?),
+                "--eval" => options.eval = Some(PathBuf::from(expand_tilde(&value("--eval")?))),
+                "--features" => {
+                    features_given = true;
+                    options.features.clear();
+                    for name in value("--features")?.split(',').map(str::trim) {
+                        let registration = features::lookup(name)?;
+                        if !options
+                            .features
+                            .iter()
+                            .any(|existing| existing.name == registration.name)
+                        {
+                            options.features.push(registration);
+                        }
+                    }
+                }
+                "--stopwords" => {
+                    options.stopwords = expand_tilde(&value("--stopwords")?).parse()?
+                }
+                "--vectors" => {
+                    options.vectors = Some(PathBuf::from(expand_tilde(&value("--vectors")?)))
+                }
+                "--vectors-idf" => options.vectors_idf = true,
+                "--normalize" => {
+                    options.normalizers = value("--normalize")?
+                        .split(',')
+                        .map(|name| name.trim().parse())
+                        .collect::<Result<_, _>>()?
+                }
+                "--lemmas" => {
+                    options.lemmas = Some(PathBuf::from(expand_tilde(&value("--lemmas")?)))
+                }
+                "--cmudict" => {
+                    options.cmudict = Some(PathBuf::from(expand_tilde(&value("--cmudict")?)))
+                }
+                "--lexicon" => options
+                    .lexicons
+                    .push(PathBuf::from(expand_tilde(&value("--lexicon")?))),
+                "--skipgram-dims" => {
+                    options.skipgram_dims = value("--skipgram-dims")?
+                        .parse()
+                        .ok()
+                        .filter(|n| *n >= 1)
+                        .ok_or("--skipgram-dims expects a positive integer")?
+                }
+                "--skipgram-epochs" => {
+                    options.skipgram_epochs = value("--skipgram-epochs")?
+                        .parse()
+                        .ok()
+                        .filter(|n| *n >= 1)
+                        .ok_or("--skipgram-epochs expects a positive integer")?
+                }
+                "--seed" => {
+                    options.seed = value("--seed")?
+                        .parse()
+                        .map_err(|_| "--seed expects a non-negative integer")?
+                }
+                "--scale" => options.scale = Some(value("--scale")?.parse()?),
+                "--scaler" => {
+                    options.scaler = Some(PathBuf::from(expand_tilde(&value("--scaler")?)))
+                }
+                "--save-scaler" => {
+                    options.save_scaler =
+                        Some(PathBuf::from(expand_tilde(&value("--save-scaler")?)))
+                }
+                "--dims" => {
+                    options.dims = Some(
+                        value("--dims")?
+                            .parse()
+                            .ok()
+                            .filter(|k| *k >= 1)
+                            .ok_or("--dims expects a positive integer")?,
+                    )
+                }
+                "--min-count" => {
+                    options.min_count = value("--min-count")?
+                        .parse()
+                        .ok()
+                        .filter(|n| *n >= 1)
+                        .ok_or("--min-count expects a positive integer")?
+                }
+                "--stats" => options.stats = true,
+                "--nbest" => {
+                    options.nbest = value("--nbest")?
+                        .parse()
+                        .ok()
+                        .filter(|n| *n >= 1)
+                        .ok_or("--nbest expects a positive integer")?
+                }
+                "--senses" => options.sense_mode = value("--senses")?.parse()?,
+                other if other.starts_with('-') && other.len() > 1 => {
+                    return Err(format!("unknown option '{other}'; see --help").into());
+                }
+                _ => {
+                    if options.word_list.is_some() {
+                        return Err(format!("unexpected extra argument '{arg}'").into());
+                    }
+                    options.word_list = Some(PathBuf::from(expand_tilde(&arg)));
+                }
+            }
+        }
+
+        if options.replace_dictionary && options.dictionaries.is_empty() {
+            return Err(
+                "--replace-dictionary requires at least one --dictionary or --kaikki file".into(),
+            );
+        }
+
+        if options.vectors.is_some() && !features_given {
+            // `basic` stays as the fallback for definitions without any
+            // word in the vectors file.
+            options.features.insert(0, features::lookup("vectors")?);
+        }
+        if options.lemmas.is_some() && !options.normalizers.contains(&Normalizer::Lemma) {
+            return Err("--lemmas needs --normalize lemma".into());
+        }
+        if !options.lexicons.is_empty() && !features_given {
+            options.features.push(features::lookup("lexicon")?);
+        }
+        if options.vectors_idf && options.vectors.is_none() {
+            return Err("--vectors-idf needs --vectors".into());
+        }
+
+        if options.scale.is_some() && options.scaler.is_some() {
+            return Err("--scale and --scaler are mutually exclusive".into());
+        }
+        if options.save_scaler.is_some() && options.scale.is_none() && options.scaler.is_none() {
+            return Err("--save-scaler needs --scale or --scaler".into());
+        }
+
+        if options.stats && options.dims.is_some() {
+            return Err(
+                "--stats describes the feature dimensions, which --dims replaces; drop one".into(),
+            );
+        }
+
+        if options.eval.is_some() && options.word_list.is_some() {
+            return Err("--eval takes its words from the gold file; drop the word list".into());
+        }
+
+        let learned = options
+            .segmenters
+            .iter()
+            .filter(|kind| kind.is_trained())
+            .count();
+        let model_options = options.train_corpus.is_some()
+            || options.segmenter_model.is_some()
+            || options.save_segmenter_model.is_some();
+        if model_options && learned == 0 {
+            return Err(
+                "--train-corpus, --segmenter-model and --save-segmenter-model \
+                        need a learned --segmenter"
+                    .into(),
+            );
+        }
+        if (options.segmenter_model.is_some() || options.save_segmenter_model.is_some())
+            && learned > 1
+        {
+            return Err(
+                "--segmenter-model and --save-segmenter-model need a single learned --segmenter"
+                    .into(),
+            );
+        }
+        if options.train_corpus.is_some() && options.segmenter_model.is_some() {
+            return Err("--train-corpus and --segmenter-model are mutually exclusive".into());
+        }
+        if options.nbest > 1
+            && let Some(kind) = options
+                .segmenters
+                .iter()
+                .find(|kind| !kind.ranks_alternatives())
+        {
+            return Err(format!(
+                "--nbest needs --segmenter viterbi; '{}' yields a single analysis",
+                kind.label()
+            )
+            .into());
+        }
+
+        Ok(options)
+    }
+}
+
+fn expand_tilde(input: &str) -> String {
+    if let Some(stripped) = input.strip_prefix("~/")
+        && let Ok(home) = std::env::var("HOME")
+    {
+        return format!("{}/{}", home.trim_end_matches('/'), stripped);
+    }
+    input.to_string()
+}
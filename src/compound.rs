@@ -0,0 +1,205 @@
+//! Compound splitting for `--compounds`: words made of free morphemes
+//! ("windowsill", "bookthief") are split into dictionary headwords, optionally
+//! joined by a linking element ("sport-s-man"), and kept as a binary tree
+//! rather than a flat list so the modifier/head structure survives.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{Morpheme, MorphemeKind};
+
+/// Parts shorter than this are never treated as free morphemes, so short
+/// headwords ("a", "an", "in") do not shred ordinary words.
+const MIN_PART_LEN: usize = 3;
+/// Extra cost of a linking element, so a plain split is preferred.
+const LINKER_COST: f32 = 0.5;
+
+/// A free morpheme or a modifier + head compound of two subtrees.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompoundTree {
+    Free(String),
+    Compound {
+        modifier: Box<CompoundTree>,
+        linker: Option<String>,
+        head: Box<CompoundTree>,
+    },
+}
+
+impl CompoundTree {
+    /// The leaves in order: every free morpheme as a root, every linking
+    /// element as a linker.
+    pub fn morphemes(&self) -> Vec<Morpheme> {
+        let mut morphemes = Vec::new();
+        self.collect(&mut morphemes);
+        morphemes
+    }
+
+    fn collect(&self, morphemes: &mut Vec<Morpheme>) {
+        match self {
+            CompoundTree::Free(word) => morphemes.push(Morpheme::new(MorphemeKind::Root, word)),
+            CompoundTree::Compound {
+                modifier,
+                linker,
+                head,
+            } => {
+                modifier.collect(morphemes);
+                if let Some(linker) = linker {
+                    morphemes.push(Morpheme::new(MorphemeKind::Linker, linker));
+                }
+                head.collect(morphemes);
+            }
+        }
+    }
+
+    fn leaves(&self) -> usize {
+        match self {
+            CompoundTree::Free(_) => 1,
+            CompoundTree::Compound { modifier, head, .. } => modifier.leaves() + head.leaves(),
+        }
+    }
+
+    fn shortest_leaf(&self) -> usize {
+        match self {
+            CompoundTree::Free(word) => word.len(),
+            CompoundTree::Compound { modifier, head, .. } => {
+                modifier.shortest_leaf().min(head.shortest_leaf())
+            }
+        }
+    }
+
+    fn linkers(&self) -> usize {
+        match self {
+            CompoundTree::Free(_) => 0,
+            CompoundTree::Compound {
+                modifier,
+                linker,
+                head,
+            } => modifier.linkers() + usize::from(linker.is_some()) + head.linkers(),
+        }
+    }
+}
+
+/// Bracketed form with the head last, e.g. `((foot ball) player)` or
+/// `(sport -s- man)`.
+impl fmt::Display for CompoundTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompoundTree::Free(word) => write!(f, "{word}"),
+            CompoundTree::Compound {
+                modifier,
+                linker: Some(linker),
+                head,
+            } => write!(f, "({modifier} -{linker}- {head})"),
+            CompoundTree::Compound {
+                modifier,
+                linker: None,
+                head,
+            } => write!(f, "({modifier} {head})"),
+        }
+    }
+}
+
+/// Splits words into free morphemes, trying each linking element between
+/// two parts.
+#[derive(Debug, Clone)]
+pub struct CompoundSplitter {
+    linkers: Vec<String>,
+}
+
+impl CompoundSplitter {
+    pub fn new(linkers: &[String]) -> Self {
+        Self {
+            linkers: linkers.to_vec(),
+        }
+    }
+
+    /// The best compound analysis of `word` in which every leaf satisfies
+    /// `is_free`, or `None` if it cannot be split into at least two free
+    /// morphemes. Fewer leaves win, then fewer linkers, then the split whose
+    /// shortest part is longest ("window sill" over "windows ill"), then the
+    /// left-branching tree.
+    pub fn split(&self, word: &str, is_free: impl Fn(&str) -> bool) -> Option<CompoundTree> {
+        let mut search = Search {
+            word,
+            linkers: &self.linkers,
+            is_free: &is_free,
+            memo: HashMap::new(),
+        };
+        search.compound(0, word.len())
+    }
+}
+
+struct Search<'a, F> {
+    word: &'a str,
+    linkers: &'a [String],
+    is_free: &'a F,
+    memo: HashMap<(usize, usize), Option<CompoundTree>>,
+}
+
+impl<F: Fn(&str) -> bool> Search<'_, F> {
+    /// Best analysis of `word[start..end]` as a free morpheme or compound.
+    fn part(&mut self, start: usize, end: usize) -> Option<CompoundTree> {
+        let text = &self.word[start..end];
+        if text.len() >= MIN_PART_LEN && (self.is_free)(text) {
+            return Some(CompoundTree::Free(text.to_string()));
+        }
+        self.compound(start, end)
+    }
+
+    /// Best analysis of `word[start..end]` with at least two parts.
+    fn compound(&mut self, start: usize, end: usize) -> Option<CompoundTree> {
+        if let Some(cached) = self.memo.get(&(start, end)) {
+            return cached.clone();
+        }
+
+        let mut best: Option<CompoundTree> = None;
+        for middle in start + MIN_PART_LEN..=end.saturating_sub(MIN_PART_LEN) {
+            let Some(modifier) = self.part(start, middle) else {
+                continue;
+            };
+            let linkers: Vec<Option<String>> = std::iter::once(None)
+                .chain(self.linkers.iter().cloned().map(Some))
+                .collect();
+            for linker in linkers {
+                let head_start = middle + linker.as_ref().map_or(0, String::len);
+                if head_start + MIN_PART_LEN > end
+                    || !self.word[middle..head_start].eq(linker.as_deref().unwrap_or(""))
+                {
+                    continue;
+                }
+                let Some(head) = self.part(head_start, end) else {
+                    continue;
+                };
+                let candidate = CompoundTree::Compound {
+                    modifier: Box::new(modifier.clone()),
+                    linker,
+                    head: Box::new(head),
+                };
+                if best
+                    .as_ref()
+                    .is_none_or(|current| score(&candidate) < score(current))
+                {
+                    best = Some(candidate);
+                }
+            }
+        }
+
+        self.memo.insert((start, end), best.clone());
+        best
+    }
+}
+
+/// Lower is better; compared lexicographically. The last key prefers
+/// left-branching trees, the usual shape of English compounds.
+fn score(tree: &CompoundTree) -> (f32, Reverse<usize>, Reverse<usize>) {
+    let modifier_leaves = match tree {
+        CompoundTree::Free(_) => 0,
+        CompoundTree::Compound { modifier, .. } => modifier.leaves(),
+    };
+    (
+        tree.leaves() as f32 + LINKER_COST * tree.linkers() as f32,
+        Reverse(tree.shortest_leaf()),
+        Reverse(modifier_leaves),
+    )
+}
@@ -0,0 +1,303 @@
+//! Derivation trees built on top of a flat segmentation.
+//! "antidisestablishmentarianism" is not five sibling morphemes but a chain
+//! of derived stems (establish → establishment → disestablishment → ...), so
+//! affixes are attached one at a time around the root, and every
+//! intermediate stem becomes a node that features can be attributed to.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::compound::CompoundTree;
+use crate::{Morpheme, MorphemeKind};
+
+/// How `--derivation` prints each word's tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DerivationStyle {
+    /// One line of nested brackets.
+    Bracketed,
+    /// An indented tree with one node per line.
+    Tree,
+}
+
+impl FromStr for DerivationStyle {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "bracketed" => Ok(DerivationStyle::Bracketed),
+            "tree" => Ok(DerivationStyle::Tree),
+            other => Err(format!(
+                "unknown derivation style '{other}' (expected 'bracketed' or 'tree')"
+            )),
+        }
+    }
+}
+
+/// A leaf morpheme or a stem derived from its parts.
+#[derive(Debug, Clone)]
+pub enum DerivationTree {
+    Leaf(Morpheme),
+    Stem {
+        /// The stem as a word: every part's surface form except the last,
+        /// which keeps its underlying form because nothing follows it yet
+        /// ("happy", not "happi").
+        form: String,
+        /// How the stem is spelled inside the word.
+        surface: String,
+        parts: Vec<DerivationTree>,
+    },
+}
+
+impl DerivationTree {
+    /// Builds the tree for `morphemes`. The core between the outermost
+    /// prefixes and suffixes is grouped first, following `compound` when the
+    /// core is a compound and branching left otherwise. Affixes are then
+    /// attached innermost first; at each step the prefix or suffix whose
+    /// result `is_word` accepts wins, and suffixes go first when that does
+    /// not decide it.
+    pub fn build(
+        morphemes: &[Morpheme],
+        compound: Option<&CompoundTree>,
+        is_word: impl Fn(&str) -> bool,
+    ) -> Option<Self> {
+        let first_core = morphemes
+            .iter()
+            .position(|morpheme| morpheme.kind != MorphemeKind::Prefix)?;
+        let end_core = morphemes
+            .iter()
+            .rposition(|morpheme| morpheme.kind != MorphemeKind::Suffix)
+            .filter(|end| *end >= first_core)?
+            + 1;
+        let mut prefixes = morphemes[..first_core].to_vec();
+        let mut suffixes = morphemes[end_core..]
+            .iter()
+            .rev()
+            .cloned()
+            .collect::<Vec<_>>();
+        let core = &morphemes[first_core..end_core];
+
+        let mut stem = match compound {
+            Some(tree) if tree.morphemes().len() == core.len() => {
+                Self::from_compound(tree, &mut core.iter().cloned())
+            }
+            _ => core
+                .iter()
+                .cloned()
+                .map(DerivationTree::Leaf)
+                .reduce(|left, right| Self::join(vec![left, right]))?,
+        };
+
+        loop {
+            let prefixed = prefixes
+                .last()
+                .map(|prefix| Self::join(vec![Self::Leaf(prefix.clone()), stem.clone()]));
+            let suffixed = suffixes
+                .last()
+                .map(|suffix| Self::join(vec![stem.clone(), Self::Leaf(suffix.clone())]));
+            stem = match (prefixed, suffixed) {
+                (None, None) => break,
+                (Some(prefixed), Some(suffixed))
+                    if !is_word(prefixed.form()) || is_word(suffixed.form()) =>
+                {
+                    suffixes.pop();
+                    suffixed
+                }
+                (Some(prefixed), _) => {
+                    prefixes.pop();
+                    prefixed
+                }
+                (None, Some(suffixed)) => {
+                    suffixes.pop();
+                    suffixed
+                }
+            };
+        }
+
+        Some(stem)
+    }
+
+    fn from_compound(
+        tree: &CompoundTree,
+        leaves: &mut impl Iterator<Item = Morpheme>,
+    ) -> DerivationTree {
+        match tree {
+            CompoundTree::Free(_) => {
+                Self::Leaf(leaves.next().expect("compound leaves match the core"))
+            }
+            CompoundTree::Compound {
+                modifier,
+                linker,
+                head,
+            } => {
+                let mut parts = vec![Self::from_compound(modifier, leaves)];
+                if linker.is_some()
+                    && let Some(morpheme) = leaves.next()
+                {
+                    parts.push(Self::Leaf(morpheme));
+                }
+                parts.push(Self::from_compound(head, leaves));
+                Self::join(parts)
+            }
+        }
+    }
+
+    fn join(parts: Vec<DerivationTree>) -> DerivationTree {
+        let surface: String = parts.iter().map(Self::surface).collect();
+        let form = match parts.last() {
+            Some(last) => {
+                let kept = surface.len() - last.surface().len();
+                format!("{}{}", &surface[..kept], last.form())
+            }
+            None => surface.clone(),
+        };
+        Self::Stem {
+            form,
+            surface,
+            parts,
+        }
+    }
+
+    /// The node as a word in its own right.
+    pub fn form(&self) -> &str {
+        match self {
+            DerivationTree::Leaf(morpheme) => &morpheme.underlying,
+            DerivationTree::Stem { form, .. } => form,
+        }
+    }
+
+    /// How the node is spelled inside a larger word; only its last morpheme
+    /// can differ from `form`.
+    fn surface(&self) -> &str {
+        match self {
+            DerivationTree::Leaf(morpheme) => &morpheme.surface,
+            DerivationTree::Stem { surface, .. } => surface,
+        }
+    }
+
+    /// Every derived stem strictly between the leaves and the whole word,
+    /// innermost first.
+    pub fn intermediate_stems(&self) -> Vec<&str> {
+        let mut stems = Vec::new();
+        if let DerivationTree::Stem { parts, .. } = self {
+            for part in parts {
+                part.collect_stems(&mut stems);
+            }
+        }
+        stems
+    }
+
+    fn collect_stems<'a>(&'a self, stems: &mut Vec<&'a str>) {
+        if let DerivationTree::Stem { form, parts, .. } = self {
+            for part in parts {
+                part.collect_stems(stems);
+            }
+            stems.push(form);
+        }
+    }
+
+    /// An indented tree view, one node per line, each prefixed by `indent`.
+    pub fn render(&self, indent: &str) -> String {
+        let mut out = String::new();
+        self.render_into(&mut out, indent, "", "");
+        out
+    }
+
+    fn render_into(&self, out: &mut String, indent: &str, branch: &str, continuation: &str) {
+        out.push_str(indent);
+        out.push_str(branch);
+        match self {
+            DerivationTree::Leaf(morpheme) => out.push_str(&morpheme.display()),
+            DerivationTree::Stem { form, .. } => out.push_str(form),
+        }
+        out.push('\n');
+        if let DerivationTree::Stem { parts, .. } = self {
+            for (index, part) in parts.iter().enumerate() {
+                let last = index + 1 == parts.len();
+                let (branch, next) = if last {
+                    ("└── ", "    ")
+                } else {
+                    ("├── ", "│   ")
+                };
+                part.render_into(
+                    out,
+                    indent,
+                    &format!("{continuation}{branch}"),
+                    &format!("{continuation}{next}"),
+                );
+            }
+        }
+    }
+}
+
+/// Bracketed view of the leaves' surface forms, e.g.
+/// `((anti ((dis (establish ment)) arian)) ism)` when "establishment",
+/// "disestablishment", "disestablishmentarian" and
+/// "antidisestablishmentarian" are words.
+impl fmt::Display for DerivationTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DerivationTree::Leaf(morpheme) => write!(f, "{}", morpheme.surface),
+            DerivationTree::Stem { parts, .. } => {
+                write!(f, "(")?;
+                for (index, part) in parts.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{part}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(morphemes: &[(MorphemeKind, &str)], words: &[&str]) -> String {
+        let morphemes: Vec<Morpheme> = morphemes
+            .iter()
+            .map(|&(kind, text)| Morpheme::new(kind, text))
+            .collect();
+        DerivationTree::build(&morphemes, None, |form| words.contains(&form))
+            .expect("the word has a core")
+            .to_string()
+    }
+
+    #[test]
+    fn affixes_attach_where_they_make_words() {
+        let morphemes = [
+            (MorphemeKind::Prefix, "anti"),
+            (MorphemeKind::Prefix, "dis"),
+            (MorphemeKind::Root, "establish"),
+            (MorphemeKind::Suffix, "ment"),
+            (MorphemeKind::Suffix, "arian"),
+            (MorphemeKind::Suffix, "ism"),
+        ];
+        let words = [
+            "establishment",
+            "disestablishment",
+            "disestablishmentarian",
+            "antidisestablishmentarian",
+        ];
+        assert_eq!(
+            build(&morphemes, &words),
+            "((anti ((dis (establish ment)) arian)) ism)"
+        );
+    }
+
+    #[test]
+    fn suffixes_attach_first_when_the_prefixed_stem_is_no_word() {
+        // "unemploy" is not a word, so "unemployment" negates "employment".
+        let morphemes = [
+            (MorphemeKind::Prefix, "un"),
+            (MorphemeKind::Root, "employ"),
+            (MorphemeKind::Suffix, "ment"),
+        ];
+        assert_eq!(build(&morphemes, &[]), "(un (employ ment))");
+        assert_eq!(build(&morphemes, &["employment"]), "(un (employ ment))");
+        // Once it is, the prefix goes on first.
+        assert_eq!(build(&morphemes, &["unemploy"]), "((un employ) ment)");
+    }
+}
@@ -0,0 +1,591 @@
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::Morpheme;
+use crate::json::{self, JsonValue};
+
+mod kaikki;
+
+pub use kaikki::KaikkiFilter;
+
+const DICTIONARY_ENTRIES: [(&str, &str); 9] = [
+    (
+        "antidisestablishmentarianism",
+        "Oxford English Dictionary (paraphrased): opposition to the withdrawal of state support or recognition from an established church.",
+    ),
+    (
+        "hypermetamorphosis",
+        "Oxford English Dictionary (paraphrased): a kind of insect development marked by distinctly different successive larval stages.",
+    ),
+    (
+        "biblioklept",
+        "Oxford English Dictionary (paraphrased): a person who steals books; a book thief.",
+    ),
+    (
+        "defenestration",
+        "Oxford English Dictionary (paraphrased): the act of throwing someone or something out of a window.",
+    ),
+    (
+        "absquatulate",
+        "Oxford English Dictionary (paraphrased): to depart abruptly; to abscond with comic haste.",
+    ),
+    (
+        "cattywampus",
+        "Oxford English Dictionary (paraphrased): askew or awry; positioned diagonally in a delightfully unruly fashion.",
+    ),
+    (
+        "transmogrification",
+        "Oxford English Dictionary (paraphrased): a transformation, especially one that is startling or magical.",
+    ),
+    (
+        "sesquipedalian",
+        "Oxford English Dictionary (paraphrased): characterized by or fond of using long words.",
+    ),
+    (
+        "kerfuffle",
+        "Oxford English Dictionary (paraphrased): a commotion or fuss, especially one caused by conflicting opinions.",
+    ),
+];
+
+/// License of the bundled definitions, left unspecified until the
+/// repository states one for its data.
+const BUILTIN_LICENSE: &str = "unspecified";
+
+/// Dictionaries whose name may open a definition as an attribution
+/// ("Oxford English Dictionary (paraphrased): ..."), with the license
+/// their text is known to carry.
+const KNOWN_SOURCES: [(&str, Option<&str>); 9] = [
+    ("Oxford English Dictionary", None),
+    ("Merriam-Webster", None),
+    ("Collins English Dictionary", None),
+    ("Cambridge Dictionary", None),
+    ("American Heritage Dictionary", None),
+    ("Wiktionary", Some("CC BY-SA 4.0")),
+    ("WordNet", Some("WordNet 3.0 license")),
+    (
+        "Webster's Revised Unabridged Dictionary",
+        Some("public domain"),
+    ),
+    ("Webster 1913", Some("public domain")),
+];
+
+/// One meaning of a headword: the gloss itself, optionally tagged with its
+/// part of speech, and where it came from.
+#[derive(Debug, Clone)]
+pub struct DictionaryEntry {
+    pub pos: Option<String>,
+    /// The gloss, without any attribution; this is all that reaches the
+    /// feature extractors.
+    pub definition: String,
+    pub source: Option<String>,
+    pub license: Option<String>,
+}
+
+impl DictionaryEntry {
+    /// Splits a leading attribution naming a known dictionary, optionally
+    /// qualified ("Oxford English Dictionary (paraphrased): ..."), off
+    /// `text` into `source`.
+    pub fn new(text: &str) -> Self {
+        let text = text.trim();
+        let mut entry = Self {
+            pos: None,
+            definition: text.to_string(),
+            source: None,
+            license: None,
+        };
+        if let Some((attribution, gloss)) = text.split_once(':')
+            && !gloss.trim().is_empty()
+        {
+            let attribution = attribution.trim();
+            let name = attribution
+                .split_once(" (")
+                .filter(|(_, qualifier)| qualifier.ends_with(')'))
+                .map_or(attribution, |(name, _)| name);
+            if let Some((_, license)) = KNOWN_SOURCES
+                .iter()
+                .find(|(known, _)| known.eq_ignore_ascii_case(name))
+            {
+                entry.definition = gloss.trim().to_string();
+                entry.source = Some(attribution.to_string());
+                entry.license = license.map(str::to_string);
+            }
+        }
+        entry
+    }
+
+    /// Source and license for provenance reports, e.g. "Wiktionary,
+    /// CC BY-SA 4.0".
+    pub fn attribution(&self) -> Option<String> {
+        match (&self.source, &self.license) {
+            (Some(source), Some(license)) => Some(format!("{source}, {license}")),
+            (Some(source), None) => Some(source.clone()),
+            (None, Some(license)) => Some(format!("unknown source, {license}")),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Every sense recorded for a headword, in source order, plus an optional
+/// reference segmentation harvested from etymology data.
+#[derive(Debug, Clone, Default)]
+pub struct Headword {
+    pub senses: Vec<DictionaryEntry>,
+    pub segmentation: Option<Vec<Morpheme>>,
+}
+
+/// Headword → entry map shared by every stage of the pipeline.
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    entries: BTreeMap<String, Headword>,
+}
+
+impl Dictionary {
+    /// The nine bundled Oxford paraphrases.
+    pub fn builtin() -> Self {
+        let mut dictionary = Self::default();
+        for (word, definition) in DICTIONARY_ENTRIES {
+            let mut sense = DictionaryEntry::new(definition);
+            sense.license = Some(BUILTIN_LICENSE.to_string());
+            dictionary.insert(
+                word,
+                Headword {
+                    senses: vec![sense],
+                    segmentation: None,
+                },
+            );
+        }
+        dictionary
+    }
+
+    pub fn get(&self, word: &str) -> Option<&Headword> {
+        self.entries.get(word)
+    }
+
+    /// Adds or replaces an entry; headwords are stored trimmed and lowercased.
+    pub fn insert(&mut self, word: &str, headword: Headword) {
+        self.entries.insert(normalize_headword(word), headword);
+    }
+
+    pub fn headwords(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// Every sense of every headword, in headword order.
+    pub fn senses(&self) -> impl Iterator<Item = &DictionaryEntry> {
+        self.entries.values().flat_map(|headword| &headword.senses)
+    }
+
+    /// How many senses each attribution contributed, unattributed senses
+    /// under `None`.
+    pub fn provenance(&self) -> BTreeMap<Option<String>, usize> {
+        let mut counts = BTreeMap::new();
+        for sense in self.senses() {
+            *counts.entry(sense.attribution()).or_default() += 1;
+        }
+        counts
+    }
+
+    /// Merges a definition file into the dictionary. The format follows the
+    /// extension: `.csv`, `.json`, `.jsonl`/`.ndjson`, anything else is TSV.
+    /// A headword defined in the file replaces any earlier entry; repeated
+    /// records within the same file add further senses. Malformed records are
+    /// collected in the report instead of failing the whole load; only I/O
+    /// errors are fatal.
+    pub fn load_file(&mut self, path: &Path) -> Result<LoadReport, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read dictionary {}: {err}", path.display()))?;
+        let mut loader = Loader::new(self);
+        match DictionaryFormat::from_path(path) {
+            DictionaryFormat::Json => loader.load_json(&contents),
+            DictionaryFormat::JsonLines => loader.load_json_lines(&contents),
+            format => loader.load_delimited(&contents, format),
+        }
+        Ok(loader.report)
+    }
+}
+
+fn normalize_headword(word: &str) -> String {
+    word.trim().to_lowercase()
+}
+
+/// Applies the records of a single file to a dictionary.
+struct Loader<'a> {
+    dictionary: &'a mut Dictionary,
+    touched: HashSet<String>,
+    report: LoadReport,
+}
+
+impl<'a> Loader<'a> {
+    fn new(dictionary: &'a mut Dictionary) -> Self {
+        Self {
+            dictionary,
+            touched: HashSet::new(),
+            report: LoadReport::default(),
+        }
+    }
+
+    fn add(&mut self, word: &str, senses: Vec<DictionaryEntry>) {
+        let key = normalize_headword(word);
+        let first_in_file = self.touched.insert(key.clone());
+        let entry = self.dictionary.entries.entry(key).or_default();
+        if first_in_file {
+            *entry = Headword::default();
+        }
+        self.report.records += 1;
+        self.report.senses += senses.len();
+        entry.senses.extend(senses);
+    }
+
+    /// Records a gold segmentation unless the headword already has one from
+    /// this file.
+    fn set_segmentation(&mut self, word: &str, segmentation: Vec<Morpheme>) {
+        if let Some(entry) = self.dictionary.entries.get_mut(&normalize_headword(word))
+            && entry.segmentation.is_none()
+        {
+            entry.segmentation = Some(segmentation);
+            self.report.segmentations += 1;
+        }
+    }
+
+    fn issue(&mut self, line: usize, message: impl Into<String>) {
+        self.report.issues.push(LineIssue::new(line, message));
+    }
+
+    fn load_delimited(&mut self, contents: &str, format: DictionaryFormat) {
+        let mut seen_record = false;
+
+        for (index, raw) in contents.lines().enumerate() {
+            let line = index + 1;
+            let trimmed = raw.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let fields = match format.split(raw) {
+                Ok(fields) => fields,
+                Err(message) => {
+                    self.issue(line, message);
+                    continue;
+                }
+            };
+
+            let first_record = !seen_record;
+            seen_record = true;
+            if first_record && is_header(&fields) {
+                continue;
+            }
+
+            match fields.as_slice() {
+                [word, definition] => {
+                    let word = word.trim();
+                    let definition = definition.trim();
+                    if word.is_empty() {
+                        self.issue(line, "empty headword");
+                    } else if definition.is_empty() {
+                        self.issue(line, format!("empty definition for '{word}'"));
+                    } else {
+                        self.add(word, vec![DictionaryEntry::new(definition)]);
+                    }
+                }
+                [_] => self.issue(
+                    line,
+                    format!("expected word{}definition", format.separator_label()),
+                ),
+                _ => self.issue(line, format!("expected 2 fields, found {}", fields.len())),
+            }
+        }
+    }
+
+    fn load_json(&mut self, contents: &str) {
+        let records = match json::parse_array_items(contents) {
+            Ok(records) => records,
+            Err(err) => {
+                self.issue(json::line_of_offset(contents, err.offset), err.message);
+                return;
+            }
+        };
+
+        for (offset, record) in records {
+            let line = json::line_of_offset(contents, offset);
+            self.load_record(line, &record);
+        }
+    }
+
+    fn load_json_lines(&mut self, contents: &str) {
+        for (index, raw) in contents.lines().enumerate() {
+            let line = index + 1;
+            if raw.trim().is_empty() {
+                continue;
+            }
+            match json::parse(raw) {
+                Ok(record) => self.load_record(line, &record),
+                Err(err) => self.issue(line, err.to_string()),
+            }
+        }
+    }
+
+    fn load_record(&mut self, line: usize, record: &JsonValue) {
+        match senses_from_record(record) {
+            Ok((word, senses)) => self.add(&word, senses),
+            Err(message) => self.issue(line, message),
+        }
+    }
+}
+
+/// Reads a `{"word": ..., "pos": ..., "senses": [...]}` record. Senses may be
+/// plain strings or objects carrying `definition`/`gloss` (plus an optional
+/// per-sense `pos`); a single top-level `definition` string is also accepted.
+/// `source` and `license` strings, on the record or on a sense, override any
+/// attribution parsed from the definition text.
+fn senses_from_record(record: &JsonValue) -> Result<(String, Vec<DictionaryEntry>), String> {
+    if !matches!(record, JsonValue::Object(_)) {
+        return Err(format!("expected an object, found {}", record.type_name()));
+    }
+    let word = record
+        .get("word")
+        .and_then(JsonValue::as_str)
+        .map(str::trim)
+        .filter(|word| !word.is_empty())
+        .ok_or("missing \"word\" string")?;
+    let record_pos = optional_string(record, "pos")?;
+    let provenance = Provenance::from_json(record)?;
+
+    let mut senses = Vec::new();
+    if let Some(definition) = optional_string(record, "definition")? {
+        let mut sense = DictionaryEntry::new(&definition);
+        provenance.apply(&mut sense);
+        senses.push(sense);
+    }
+    match record.get("senses") {
+        None => {}
+        Some(JsonValue::Array(items)) => {
+            for (index, item) in items.iter().enumerate() {
+                senses
+                    .push(sense_from_json(item, &provenance).map_err(|message| {
+                        format!("sense {} of '{word}': {message}", index + 1)
+                    })?);
+            }
+        }
+        Some(other) => {
+            return Err(format!(
+                "\"senses\" must be an array, found {}",
+                other.type_name()
+            ));
+        }
+    }
+
+    let senses: Vec<DictionaryEntry> = senses
+        .into_iter()
+        .map(|mut sense| {
+            sense.pos = sense.pos.or_else(|| record_pos.clone());
+            sense
+        })
+        .collect();
+    if senses.is_empty() {
+        return Err(format!("no definitions for '{word}'"));
+    }
+    Ok((word.to_string(), senses))
+}
+
+fn sense_from_json(item: &JsonValue, record: &Provenance) -> Result<DictionaryEntry, String> {
+    let (definition, pos, provenance) = match item {
+        JsonValue::String(text) => (Some(text.clone()), None, Provenance::default()),
+        JsonValue::Object(_) => (
+            optional_string(item, "definition")?.or(optional_string(item, "gloss")?),
+            optional_string(item, "pos")?,
+            Provenance::from_json(item)?,
+        ),
+        other => {
+            return Err(format!(
+                "expected string or object, found {}",
+                other.type_name()
+            ));
+        }
+    };
+    let definition = definition
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+        .ok_or("empty definition")?;
+    let mut sense = DictionaryEntry::new(&definition);
+    sense.pos = pos;
+    record.apply(&mut sense);
+    provenance.apply(&mut sense);
+    Ok(sense)
+}
+
+/// Explicit `source`/`license` fields of a JSON record or sense.
+#[derive(Debug, Default)]
+struct Provenance {
+    source: Option<String>,
+    license: Option<String>,
+}
+
+impl Provenance {
+    fn from_json(item: &JsonValue) -> Result<Self, String> {
+        Ok(Self {
+            source: optional_string(item, "source")?,
+            license: optional_string(item, "license")?,
+        })
+    }
+
+    fn apply(&self, sense: &mut DictionaryEntry) {
+        if let Some(source) = &self.source {
+            sense.source = Some(source.clone());
+        }
+        if let Some(license) = &self.license {
+            sense.license = Some(license.clone());
+        }
+    }
+}
+
+fn optional_string(record: &JsonValue, key: &str) -> Result<Option<String>, String> {
+    match record.get(key) {
+        None | Some(JsonValue::Null) => Ok(None),
+        Some(JsonValue::String(text)) => Ok(Some(text.trim().to_string())),
+        Some(other) => Err(format!(
+            "\"{key}\" must be a string, found {}",
+            other.type_name()
+        )),
+    }
+}
+
+/// Outcome of loading a single dictionary file.
+#[derive(Debug, Default)]
+pub struct LoadReport {
+    /// Headword records accepted from the file.
+    pub records: usize,
+    /// Senses contributed by those records.
+    pub senses: usize,
+    /// Gold segmentations harvested alongside the definitions.
+    pub segmentations: usize,
+    pub issues: Vec<LineIssue>,
+}
+
+/// A malformed line that was skipped during loading.
+#[derive(Debug)]
+pub struct LineIssue {
+    pub line: usize,
+    pub message: String,
+}
+
+impl LineIssue {
+    pub fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for LineIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DictionaryFormat {
+    Tsv,
+    Csv,
+    Json,
+    JsonLines,
+}
+
+impl DictionaryFormat {
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => DictionaryFormat::Csv,
+            Some(ext) if ext.eq_ignore_ascii_case("json") => DictionaryFormat::Json,
+            Some(ext)
+                if ext.eq_ignore_ascii_case("jsonl") || ext.eq_ignore_ascii_case("ndjson") =>
+            {
+                DictionaryFormat::JsonLines
+            }
+            _ => DictionaryFormat::Tsv,
+        }
+    }
+
+    fn split(self, line: &str) -> Result<Vec<String>, String> {
+        match self {
+            DictionaryFormat::Csv => split_csv_record(line),
+            _ => Ok(match line.split_once('\t') {
+                Some((word, definition)) => vec![word.to_string(), definition.to_string()],
+                None => vec![line.to_string()],
+            }),
+        }
+    }
+
+    fn separator_label(self) -> &'static str {
+        match self {
+            DictionaryFormat::Csv => ",",
+            _ => "<TAB>",
+        }
+    }
+}
+
+fn is_header(fields: &[String]) -> bool {
+    matches!(fields, [word, definition]
+        if word.trim().eq_ignore_ascii_case("word")
+            && definition.trim().eq_ignore_ascii_case("definition"))
+}
+
+/// Splits one CSV record following RFC 4180 quoting: fields may be wrapped in
+/// double quotes, and `""` inside a quoted field is a literal quote.
+fn split_csv_record(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    loop {
+        let mut field = String::new();
+        while chars.peek().is_some_and(|c| *c == ' ') {
+            chars.next();
+        }
+
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return Err("unterminated quoted field".to_string()),
+                }
+            }
+            while chars.peek().is_some_and(|c| *c == ' ') {
+                chars.next();
+            }
+            match chars.next() {
+                Some(',') => {
+                    fields.push(field);
+                    continue;
+                }
+                None => {
+                    fields.push(field);
+                    break;
+                }
+                Some(c) => return Err(format!("unexpected '{c}' after closing quote")),
+            }
+        }
+
+        let mut terminated = false;
+        for c in chars.by_ref() {
+            if c == ',' {
+                terminated = true;
+                break;
+            }
+            field.push(c);
+        }
+        fields.push(field);
+        if !terminated {
+            break;
+        }
+    }
+
+    Ok(fields)
+}
@@ -0,0 +1,214 @@
+//! Streaming importer for Kaikki.org-style Wiktionary extracts: one JSON
+//! object per line with `word`, `lang_code`, `pos`, `senses[].glosses` and
+//! `etymology_templates`.
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use super::{Dictionary, DictionaryEntry, LoadReport, Loader};
+use crate::json::{self, JsonValue};
+use crate::{Morpheme, MorphemeKind};
+
+/// Attribution of every imported gloss.
+const SOURCE: &str = "Wiktionary (Kaikki.org)";
+const LICENSE: &str = "CC BY-SA 4.0";
+
+/// Which records of a dump to keep.
+#[derive(Debug, Clone)]
+pub struct KaikkiFilter {
+    /// Wiktionary language code, e.g. `en`.
+    pub lang_code: String,
+    /// Parts of speech to keep; empty keeps every part of speech.
+    pub parts_of_speech: Vec<String>,
+}
+
+impl Default for KaikkiFilter {
+    fn default() -> Self {
+        Self {
+            lang_code: "en".to_string(),
+            parts_of_speech: Vec::new(),
+        }
+    }
+}
+
+impl KaikkiFilter {
+    fn accepts(&self, record: &JsonValue) -> bool {
+        let lang_matches =
+            record.get("lang_code").and_then(JsonValue::as_str) == Some(self.lang_code.as_str());
+        let pos_matches = self.parts_of_speech.is_empty()
+            || record
+                .get("pos")
+                .and_then(JsonValue::as_str)
+                .is_some_and(|pos| self.parts_of_speech.iter().any(|wanted| wanted == pos));
+        lang_matches && pos_matches
+    }
+}
+
+impl Dictionary {
+    /// Streams a Kaikki JSONL dump into the dictionary, keeping records that
+    /// pass `filter`. Sense glosses become senses and the first morphological
+    /// etymology template of a headword becomes its gold segmentation.
+    pub fn import_kaikki(
+        &mut self,
+        path: &Path,
+        filter: &KaikkiFilter,
+    ) -> Result<LoadReport, Box<dyn Error>> {
+        let file = File::open(path)
+            .map_err(|err| format!("cannot open Kaikki dump {}: {err}", path.display()))?;
+        let mut reader = BufReader::new(file);
+        let mut loader = Loader::new(self);
+        let lang_marker = format!("\"{}\"", filter.lang_code);
+        let mut buffer = Vec::new();
+        let mut line = 0;
+
+        loop {
+            buffer.clear();
+            if reader.read_until(b'\n', &mut buffer)? == 0 {
+                break;
+            }
+            line += 1;
+
+            let text = String::from_utf8_lossy(&buffer);
+            // Cheap pre-filter so records in other languages are never parsed.
+            if text.trim().is_empty() || !text.contains(&lang_marker) {
+                continue;
+            }
+
+            let record = match json::parse(text.trim_end()) {
+                Ok(record) => record,
+                Err(err) => {
+                    loader.issue(line, err.to_string());
+                    continue;
+                }
+            };
+            if !filter.accepts(&record) {
+                continue;
+            }
+            let Some(word) = record.get("word").and_then(JsonValue::as_str) else {
+                loader.issue(line, "missing \"word\" string");
+                continue;
+            };
+
+            let senses = senses_from_record(&record);
+            if senses.is_empty() {
+                continue;
+            }
+            loader.add(word, senses);
+
+            if let Some(segmentation) = segmentation_from_record(&record, &filter.lang_code) {
+                loader.set_segmentation(word, segmentation);
+            }
+        }
+
+        Ok(loader.report)
+    }
+}
+
+fn senses_from_record(record: &JsonValue) -> Vec<DictionaryEntry> {
+    let pos = record
+        .get("pos")
+        .and_then(JsonValue::as_str)
+        .map(str::to_string);
+    let Some(JsonValue::Array(senses)) = record.get("senses") else {
+        return Vec::new();
+    };
+
+    senses
+        .iter()
+        .filter_map(|sense| match sense.get("glosses") {
+            // Nested senses repeat their parent glosses first; the last
+            // gloss is the most specific one.
+            Some(JsonValue::Array(glosses)) => glosses.last().and_then(JsonValue::as_str),
+            _ => None,
+        })
+        .map(str::trim)
+        .filter(|gloss| !gloss.is_empty())
+        .map(|gloss| DictionaryEntry {
+            pos: pos.clone(),
+            definition: gloss.to_string(),
+            source: Some(SOURCE.to_string()),
+            license: Some(LICENSE.to_string()),
+        })
+        .collect()
+}
+
+/// Reads the first `affix`/`prefix`/`suffix`/`confix`/`compound` template
+/// (or their short aliases) written for `lang_code`.
+fn segmentation_from_record(record: &JsonValue, lang_code: &str) -> Option<Vec<Morpheme>> {
+    let Some(JsonValue::Array(templates)) = record.get("etymology_templates") else {
+        return None;
+    };
+
+    templates.iter().find_map(|template| {
+        let name = template.get("name").and_then(JsonValue::as_str)?;
+        let args = template.get("args")?;
+        if args.get("1").and_then(JsonValue::as_str) != Some(lang_code) {
+            return None;
+        }
+        segmentation_from_template(name, &positional_parts(args))
+    })
+}
+
+/// Collects the numbered arguments after the language code, in order.
+fn positional_parts(args: &JsonValue) -> Vec<String> {
+    let JsonValue::Object(fields) = args else {
+        return Vec::new();
+    };
+    let mut numbered: Vec<(usize, &str)> = fields
+        .iter()
+        .filter_map(|(key, value)| Some((key.parse::<usize>().ok()?, value.as_str()?)))
+        .filter(|(index, _)| *index >= 2)
+        .collect();
+    numbered.sort_by_key(|(index, _)| *index);
+    numbered
+        .into_iter()
+        .map(|(_, part)| part.trim().to_lowercase())
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+fn segmentation_from_template(name: &str, parts: &[String]) -> Option<Vec<Morpheme>> {
+    let morphological = matches!(
+        name,
+        "affix"
+            | "af"
+            | "prefix"
+            | "pre"
+            | "suffix"
+            | "suf"
+            | "confix"
+            | "con"
+            | "compound"
+            | "com"
+    );
+    if !morphological || parts.len() < 2 {
+        return None;
+    }
+
+    let last = parts.len() - 1;
+    let default_kind = |index: usize| match name {
+        "prefix" | "pre" if index < last => MorphemeKind::Prefix,
+        "suffix" | "suf" if index > 0 => MorphemeKind::Suffix,
+        "confix" | "con" if index == 0 => MorphemeKind::Prefix,
+        "confix" | "con" if index == last => MorphemeKind::Suffix,
+        _ => MorphemeKind::Root,
+    };
+
+    let morphemes = parts
+        .iter()
+        .enumerate()
+        .map(|(index, part)| {
+            // Explicit hyphens (`anti-`, `-ism`) override the template's roles.
+            let kind = match (part.starts_with('-'), part.ends_with('-')) {
+                (false, true) => MorphemeKind::Prefix,
+                (true, false) => MorphemeKind::Suffix,
+                _ => default_kind(index),
+            };
+            Morpheme::new(kind, part.trim_matches('-'))
+        })
+        .filter(|morpheme| !morpheme.surface.is_empty())
+        .collect::<Vec<_>>();
+    (morphemes.len() >= 2).then_some(morphemes)
+}
@@ -0,0 +1,222 @@
+//! Gold-standard evaluation for `--eval`: segmenter output is compared with
+//! reference segmentations on morpheme boundaries, the measure used by the
+//! Morpho Challenge segmentation tasks.
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::clean_word;
+use crate::dictionary::LineIssue;
+
+/// A gold word with every reference analysis given for it.
+#[derive(Debug, Clone)]
+pub struct GoldEntry {
+    pub word: String,
+    /// Surface morphs of each alternative analysis.
+    pub analyses: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Default)]
+pub struct GoldStandard {
+    pub entries: Vec<GoldEntry>,
+    pub issues: Vec<LineIssue>,
+}
+
+impl GoldStandard {
+    /// Reads Morpho Challenge style lines, `word<TAB>analysis, analysis`,
+    /// where each analysis lists whitespace separated morphs and a morph may
+    /// carry a label after a colon (`abandon:abandon_V ed:+PAST`). Only the
+    /// surface part before the colon is used. Blank lines and `#` comments
+    /// are skipped; malformed lines are reported and skipped.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read gold file {}: {err}", path.display()))?;
+        let mut gold = Self::default();
+
+        for (index, line) in contents.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((word, analyses)) = line.split_once('\t') else {
+                gold.issues
+                    .push(LineIssue::new(line_number, "expected word<TAB>analyses"));
+                continue;
+            };
+            let word = clean_word(word);
+            if word.is_empty() {
+                gold.issues
+                    .push(LineIssue::new(line_number, "word has no letters"));
+                continue;
+            }
+
+            let mut entry = GoldEntry {
+                word,
+                analyses: Vec::new(),
+            };
+            for analysis in analyses.split(',') {
+                let morphs: Vec<String> = analysis
+                    .split_whitespace()
+                    .map(|morph| clean_word(morph.split(':').next().unwrap_or(morph)))
+                    .filter(|morph| !morph.is_empty())
+                    .collect();
+                if morphs.concat() == entry.word {
+                    entry.analyses.push(morphs);
+                } else {
+                    gold.issues.push(LineIssue::new(
+                        line_number,
+                        format!(
+                            "analysis '{}' does not spell '{}'",
+                            analysis.trim(),
+                            entry.word
+                        ),
+                    ));
+                }
+            }
+            if !entry.analyses.is_empty() {
+                gold.entries.push(entry);
+            }
+        }
+
+        Ok(gold)
+    }
+}
+
+/// A word whose predicted boundaries differ from the closest gold analysis.
+#[derive(Debug, Clone)]
+pub struct Mistake {
+    pub word: String,
+    pub predicted: Vec<usize>,
+    pub gold: Vec<usize>,
+}
+
+impl Mistake {
+    /// Boundaries predicted but absent from the gold analysis.
+    pub fn spurious(&self) -> usize {
+        self.predicted
+            .iter()
+            .filter(|offset| !self.gold.contains(offset))
+            .count()
+    }
+
+    /// Gold boundaries the segmenter did not predict.
+    pub fn missed(&self) -> usize {
+        self.gold
+            .iter()
+            .filter(|offset| !self.predicted.contains(offset))
+            .count()
+    }
+}
+
+/// Micro-averaged boundary counts over every evaluated word.
+#[derive(Debug, Default)]
+pub struct Evaluation {
+    pub words: usize,
+    pub exact: usize,
+    pub hits: usize,
+    pub predicted: usize,
+    pub gold: usize,
+    pub mistakes: Vec<Mistake>,
+}
+
+impl Evaluation {
+    /// Scores one word. With several gold analyses the one sharing the most
+    /// boundaries with the prediction is used, as in Morpho Challenge.
+    pub fn add(&mut self, entry: &GoldEntry, predicted_morphs: &[&str]) {
+        let predicted = boundaries(predicted_morphs.iter().copied());
+        let Some(gold) = entry
+            .analyses
+            .iter()
+            .map(|analysis| boundaries(analysis.iter().map(String::as_str)))
+            .max_by_key(|gold| {
+                let hits = count_hits(&predicted, gold);
+                // Prefer the most hits, then the fewest unmatched boundaries.
+                (
+                    hits,
+                    std::cmp::Reverse(gold.len() + predicted.len() - 2 * hits),
+                )
+            })
+        else {
+            return;
+        };
+
+        self.words += 1;
+        self.hits += count_hits(&predicted, &gold);
+        self.predicted += predicted.len();
+        self.gold += gold.len();
+        if predicted == gold {
+            self.exact += 1;
+        } else {
+            self.mistakes.push(Mistake {
+                word: entry.word.clone(),
+                predicted,
+                gold,
+            });
+        }
+    }
+
+    pub fn precision(&self) -> f64 {
+        ratio(self.hits, self.predicted)
+    }
+
+    pub fn recall(&self) -> f64 {
+        ratio(self.hits, self.gold)
+    }
+
+    pub fn f1(&self) -> f64 {
+        let (precision, recall) = (self.precision(), self.recall());
+        if precision + recall == 0.0 {
+            0.0
+        } else {
+            2.0 * precision * recall / (precision + recall)
+        }
+    }
+
+    pub fn exact_rate(&self) -> f64 {
+        ratio(self.exact, self.words)
+    }
+}
+
+/// Internal boundary offsets (in bytes) of a sequence of morphs.
+pub fn boundaries<'a>(morphs: impl IntoIterator<Item = &'a str>) -> Vec<usize> {
+    let mut offsets: Vec<usize> = morphs
+        .into_iter()
+        .scan(0, |offset, morph| {
+            *offset += morph.len();
+            Some(*offset)
+        })
+        .collect();
+    offsets.pop();
+    offsets
+}
+
+/// `word` with a `|` at every boundary, e.g. `happi|ness`.
+pub fn mark(word: &str, boundaries: &[usize]) -> String {
+    let mut marked = String::with_capacity(word.len() + boundaries.len());
+    let mut start = 0;
+    for &offset in boundaries {
+        marked.push_str(&word[start..offset]);
+        marked.push('|');
+        start = offset;
+    }
+    marked.push_str(&word[start..]);
+    marked
+}
+
+fn count_hits(predicted: &[usize], gold: &[usize]) -> usize {
+    predicted
+        .iter()
+        .filter(|offset| gold.contains(offset))
+        .count()
+}
+
+/// `part / whole`, treating an empty whole as perfect (nothing to get wrong).
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        1.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
@@ -0,0 +1,1027 @@
+//! Definition feature extractors. Each extractor turns a definition into a
+//! fixed number of named dimensions; `--features` picks extractors from the
+//! registry by name and concatenates their vectors, so every morpheme
+//! embedding can be read back column by column. Vectors are sparse because
+//! the bag-of-words extractors have one dimension per vocabulary word.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use crate::cli::Options;
+use crate::dictionary::DictionaryEntry;
+use crate::lexicon::Lexicon;
+use crate::pos::{self, PosTag};
+use crate::read_words_from_file;
+use crate::scaling::Scaler;
+use crate::skipgram::{SkipGram, SkipGramConfig};
+use crate::syllables::Syllabifier;
+use crate::tokenizer::{Document, Tokenizer};
+use crate::vectors::WordVectors;
+
+/// Common English function words dropped by the bag-of-words extractors
+/// unless `--stopwords` says otherwise.
+const STOPWORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "an", "and", "any", "are", "as", "at", "be", "been",
+    "being", "but", "by", "can", "could", "did", "do", "does", "each", "for", "from", "had", "has",
+    "have", "he", "her", "his", "how", "if", "in", "into", "is", "it", "its", "may", "more",
+    "most", "no", "not", "of", "on", "one", "or", "other", "our", "out", "she", "so", "some",
+    "such", "than", "that", "the", "their", "them", "then", "there", "these", "they", "this",
+    "those", "to", "up", "was", "we", "were", "what", "when", "which", "while", "who", "whom",
+    "will", "with", "would", "you", "your",
+];
+
+/// Turns a definition into a vector whose length and meaning are fixed by
+/// `dimensions`.
+pub trait FeatureExtractor {
+    /// Registry name, also used to qualify dimension names.
+    fn name(&self) -> &'static str;
+
+    /// Learns whatever the extractor needs from every loaded definition
+    /// before the first `extract`. Word lists of the extractor's
+    /// own go through `tokenizer` here so that they match those tokens. Most
+    /// extractors need nothing.
+    fn fit(
+        &mut self,
+        _documents: &[Document],
+        _tokenizer: &Tokenizer,
+    ) -> Result<FitReport, Box<dyn Error>> {
+        Ok(FitReport::default())
+    }
+
+    /// One name per output dimension, in order.
+    fn dimensions(&self) -> Vec<String>;
+
+    fn extract(&self, document: &Document) -> SparseVector;
+}
+
+/// What `fit` did, for the log.
+#[derive(Debug, Default)]
+pub struct FitReport {
+    pub summary: Option<String>,
+    /// Problems in the extractor's own input files, as `path:line: message`.
+    pub warnings: Vec<String>,
+}
+
+/// Builds an extractor from the options and the syllabifier that main
+/// loaded once for every extractor that counts syllables.
+type Builder = fn(&Options, &Rc<Syllabifier>) -> Result<Box<dyn FeatureExtractor>, Box<dyn Error>>;
+
+/// A built-in extractor that `--features` can name.
+#[derive(Debug, Clone, Copy)]
+pub struct Registration {
+    pub name: &'static str,
+    build: Builder,
+}
+
+pub const REGISTRY: &[Registration] = &[
+    Registration {
+        name: "basic",
+        build: |_, syllabifier| Ok(Box::new(BasicStats::new(syllabifier))),
+    },
+    Registration {
+        name: "readability",
+        build: |_, syllabifier| Ok(Box::new(Readability::new(syllabifier))),
+    },
+    Registration {
+        name: "lexicon",
+        build: |options, _| Ok(Box::new(LexiconFeatures::new(options))),
+    },
+    Registration {
+        name: "pos",
+        build: |_, _| Ok(Box::new(PartOfSpeech)),
+    },
+    Registration {
+        name: "bow",
+        build: |options, _| Ok(Box::new(BagOfWords::new(Weighting::Counts, options)?)),
+    },
+    Registration {
+        name: "tfidf",
+        build: |options, _| Ok(Box::new(BagOfWords::new(Weighting::TfIdf, options)?)),
+    },
+    Registration {
+        name: "skipgram",
+        build: |options, _| Ok(Box::new(SkipGramFeatures::new(options)?)),
+    },
+    Registration {
+        name: "vectors",
+        build: |options, _| Ok(Box::new(PretrainedVectors::new(options)?)),
+    },
+];
+
+/// The registered extractor called `name`.
+pub fn lookup(name: &str) -> Result<&'static Registration, String> {
+    REGISTRY
+        .iter()
+        .find(|registration| registration.name == name)
+        .ok_or_else(|| {
+            let known = REGISTRY
+                .iter()
+                .map(|registration| format!("'{}'", registration.name))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("unknown feature extractor '{name}' (expected one of {known})")
+        })
+}
+
+/// Several extractors run in order over one tokenization, their vectors
+/// concatenated and optionally scaled.
+pub struct FeatureSet {
+    tokenizer: Tokenizer,
+    extractors: Vec<Box<dyn FeatureExtractor>>,
+    /// Each extractor's dimension count, fixed once it is fitted, so that
+    /// `extract` need not rebuild vocabulary-sized name lists.
+    widths: Vec<usize>,
+    scaler: Option<Scaler>,
+}
+
+impl FeatureSet {
+    /// The extractors chosen with `--features`, in order.
+    pub fn new(options: &Options, syllabifier: &Rc<Syllabifier>) -> Result<Self, Box<dyn Error>> {
+        let extractors = options
+            .features
+            .iter()
+            .map(|registration| (registration.build)(options, syllabifier))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            tokenizer: Tokenizer::new(options)?,
+            extractors,
+            widths: Vec::new(),
+            scaler: None,
+        })
+    }
+
+    /// Fits the tokenizer, then every extractor in turn; the reports come
+    /// back in extractor order.
+    pub fn fit(&mut self, entries: &[&DictionaryEntry]) -> Result<Vec<FitReport>, Box<dyn Error>> {
+        let definitions: Vec<&str> = entries
+            .iter()
+            .map(|entry| entry.definition.as_str())
+            .collect();
+        self.tokenizer.fit(&definitions);
+        let documents: Vec<Document> = entries.iter().map(|entry| self.document(entry)).collect();
+        let reports = self
+            .extractors
+            .iter_mut()
+            .map(|extractor| extractor.fit(&documents, &self.tokenizer))
+            .collect();
+        self.widths = self
+            .extractors
+            .iter()
+            .map(|extractor| extractor.dimensions().len())
+            .collect();
+        reports
+    }
+
+    /// Every dimension as `extractor.dimension`.
+    pub fn dimension_names(&self) -> Vec<String> {
+        self.extractors
+            .iter()
+            .flat_map(|extractor| {
+                extractor
+                    .dimensions()
+                    .into_iter()
+                    .map(|dimension| format!("{}.{dimension}", extractor.name()))
+            })
+            .collect()
+    }
+
+    fn document<'a>(&self, entry: &'a DictionaryEntry) -> Document<'a> {
+        Document {
+            pos: entry.pos.as_deref(),
+            ..self.tokenizer.tokenize(&entry.definition)
+        }
+    }
+
+    /// Scales every vector `extract` returns from now on. The scaler must
+    /// have been fitted on `dimension_names`.
+    pub fn set_scaler(&mut self, scaler: Scaler) {
+        self.scaler = Some(scaler);
+    }
+
+    /// The concatenation of every extractor's vector, scaled if a scaler is
+    /// set. Only valid after `fit`.
+    pub fn extract(&self, entry: &DictionaryEntry) -> SparseVector {
+        debug_assert_eq!(self.widths.len(), self.extractors.len(), "not fitted");
+        let document = self.document(entry);
+        let mut entries = Vec::new();
+        let mut offset = 0;
+        for (extractor, width) in self.extractors.iter().zip(&self.widths) {
+            let vector = extractor.extract(&document);
+            entries.extend(
+                vector
+                    .entries()
+                    .iter()
+                    .map(|&(index, value)| (offset + index, value)),
+            );
+            offset += width;
+        }
+        let vector = SparseVector { entries };
+        match &self.scaler {
+            Some(scaler) => scaler.transform(&vector),
+            None => vector,
+        }
+    }
+}
+
+/// A feature vector holding only its non-zero entries, sorted by index.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SparseVector {
+    entries: Vec<(usize, f32)>,
+}
+
+impl SparseVector {
+    /// Builds a vector from `(index, value)` pairs in any order; zeros are
+    /// dropped and repeated indices must not occur.
+    pub fn from_entries(mut entries: Vec<(usize, f32)>) -> Self {
+        entries.retain(|(_, value)| *value != 0.0);
+        entries.sort_by_key(|(index, _)| *index);
+        Self { entries }
+    }
+
+    pub fn entries(&self) -> &[(usize, f32)] {
+        &self.entries
+    }
+
+    pub fn to_dense(&self, dimensions: usize) -> Vec<f32> {
+        let mut dense = vec![0.0; dimensions];
+        for &(index, value) in &self.entries {
+            dense[index] = value;
+        }
+        dense
+    }
+}
+
+impl From<Vec<f32>> for SparseVector {
+    fn from(dense: Vec<f32>) -> Self {
+        Self::from_entries(dense.into_iter().enumerate().collect())
+    }
+}
+
+/// Surface statistics of the definition: length, vocabulary leaning and a
+/// rough complexity score.
+#[derive(Debug, Clone)]
+pub struct BasicStats {
+    lexicon: Lexicon,
+    syllabifier: Rc<Syllabifier>,
+}
+
+impl BasicStats {
+    pub fn new(syllabifier: &Rc<Syllabifier>) -> Self {
+        Self {
+            lexicon: Lexicon::builtin(),
+            syllabifier: Rc::clone(syllabifier),
+        }
+    }
+}
+
+impl FeatureExtractor for BasicStats {
+    fn name(&self) -> &'static str {
+        "basic"
+    }
+
+    fn fit(
+        &mut self,
+        documents: &[Document],
+        tokenizer: &Tokenizer,
+    ) -> Result<FitReport, Box<dyn Error>> {
+        self.lexicon.normalize(|word| tokenizer.normalize(word));
+        self.lexicon.add_lemmas(documents, tokenizer);
+        Ok(FitReport::default())
+    }
+
+    fn dimensions(&self) -> Vec<String> {
+        [
+            "word_count",
+            "mean_token_length",
+            "sensory_ratio",
+            "abstract_ratio",
+            "complexity",
+        ]
+        .map(str::to_string)
+        .to_vec()
+    }
+
+    fn extract(&self, document: &Document) -> SparseVector {
+        let tokens = &document.tokens;
+        if tokens.is_empty() {
+            return SparseVector::default();
+        }
+
+        let word_count = tokens.len() as f32;
+        let total_chars: usize = tokens.iter().map(|token| token.len()).sum();
+        let avg_word_length = total_chars as f32 / word_count;
+
+        let [sensory_ratio, abstract_ratio] = self.lexicon.scores(tokens)[..] else {
+            unreachable!("the builtin lexicon has two categories");
+        };
+
+        let unique_tokens = tokens.iter().collect::<HashSet<_>>().len() as f32;
+
+        let polysyllables = document
+            .words()
+            .iter()
+            .filter(|word| self.syllabifier.count(word) >= 3)
+            .count() as f32;
+
+        vec![
+            word_count,
+            avg_word_length,
+            sensory_ratio,
+            abstract_ratio,
+            unique_tokens / word_count.max(1.0) + polysyllables / word_count,
+        ]
+        .into()
+    }
+}
+
+/// Readability of the definition from its sentence length and syllable
+/// counts.
+#[derive(Debug, Clone)]
+struct Readability {
+    syllabifier: Rc<Syllabifier>,
+}
+
+impl Readability {
+    fn new(syllabifier: &Rc<Syllabifier>) -> Self {
+        Self {
+            syllabifier: Rc::clone(syllabifier),
+        }
+    }
+
+    /// Whether `word` counts as complex for the Gunning fog index: three or
+    /// more syllables, not counting an inflectional "-es", "-ed" or "-ing".
+    fn is_complex(&self, word: &str) -> bool {
+        if self.syllabifier.count(word) < 3 {
+            return false;
+        }
+        ["es", "ed", "ing"]
+            .iter()
+            .filter_map(|suffix| word.strip_suffix(suffix))
+            .all(|stem| self.syllabifier.count(stem) >= 3)
+    }
+}
+
+impl FeatureExtractor for Readability {
+    fn name(&self) -> &'static str {
+        "readability"
+    }
+
+    fn dimensions(&self) -> Vec<String> {
+        [
+            "syllables_per_word",
+            "words_per_sentence",
+            "flesch_kincaid_grade",
+            "gunning_fog",
+        ]
+        .map(str::to_string)
+        .to_vec()
+    }
+
+    /// Syllables come from the words as written, since normalized tokens
+    /// may be stems. Sentences end at `.`, `!`, `?` or `;`, and a
+    /// definition without any terminator is one sentence.
+    fn extract(&self, document: &Document) -> SparseVector {
+        let words = document.words();
+        if words.is_empty() {
+            return SparseVector::default();
+        }
+        let sentences = document
+            .text
+            .split(['.', '!', '?', ';'])
+            .filter(|sentence| sentence.chars().any(|c| c.is_ascii_alphabetic()))
+            .count()
+            .max(1) as f32;
+        let word_count = words.len() as f32;
+        let syllables: usize = words.iter().map(|word| self.syllabifier.count(word)).sum();
+        let complex = words.iter().filter(|word| self.is_complex(word)).count() as f32;
+
+        let syllables_per_word = syllables as f32 / word_count;
+        let words_per_sentence = word_count / sentences;
+        vec![
+            syllables_per_word,
+            words_per_sentence,
+            0.39 * words_per_sentence + 11.8 * syllables_per_word - 15.59,
+            0.4 * (words_per_sentence + 100.0 * complex / word_count),
+        ]
+        .into()
+    }
+}
+
+/// The tokens that are not stopwords.
+fn content_words(tokens: &[String], stopwords: &HashSet<String>) -> Vec<String> {
+    tokens
+        .iter()
+        .filter(|token| !stopwords.contains(*token))
+        .cloned()
+        .collect()
+}
+
+/// `words` as the tokenizer would normalize them.
+fn normalize_words(words: &HashSet<String>, tokenizer: &Tokenizer) -> HashSet<String> {
+    words.iter().map(|word| tokenizer.normalize(word)).collect()
+}
+
+/// In how many documents each word appears.
+fn document_frequencies(documents: &[Vec<String>]) -> BTreeMap<String, usize> {
+    let mut frequencies = BTreeMap::new();
+    for document in documents {
+        let unique: HashSet<&String> = document.iter().collect();
+        for word in unique {
+            *frequencies.entry(word.clone()).or_default() += 1;
+        }
+    }
+    frequencies
+}
+
+/// Smoothed inverse document frequency `ln((1 + N) / (1 + df))`, which is
+/// zero for a word found in every document.
+fn idf(documents: usize, document_frequency: usize) -> f32 {
+    ((1.0 + documents as f32) / (1.0 + document_frequency as f32)).ln()
+}
+
+/// Which words the bag-of-words, skip-gram and word-vector extractors
+/// ignore.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Stopwords {
+    /// The built-in English list.
+    #[default]
+    Builtin,
+    /// Keep every word.
+    None,
+    /// A word list file, one word per line.
+    File(PathBuf),
+}
+
+impl Stopwords {
+    fn load(&self) -> Result<HashSet<String>, Box<dyn Error>> {
+        Ok(match self {
+            Stopwords::Builtin => STOPWORDS.iter().map(|word| word.to_string()).collect(),
+            Stopwords::None => HashSet::new(),
+            Stopwords::File(path) => read_words_from_file(path)
+                .map_err(|err| format!("cannot read stopwords {}: {err}", path.display()))?
+                .into_iter()
+                .map(|word| word.to_lowercase())
+                .collect(),
+        })
+    }
+}
+
+impl FromStr for Stopwords {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(match value {
+            "builtin" => Stopwords::Builtin,
+            "none" => Stopwords::None,
+            path => Stopwords::File(PathBuf::from(path)),
+        })
+    }
+}
+
+/// How a bag-of-words dimension is weighted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Weighting {
+    /// Raw term counts.
+    Counts,
+    /// Term counts times smoothed inverse document frequency, L2-normalised.
+    TfIdf,
+}
+
+/// One dimension per word of a vocabulary shared by every loaded
+/// definition, so definitions can only resemble each other through the
+/// words they actually share.
+#[derive(Debug, Clone)]
+struct BagOfWords {
+    weighting: Weighting,
+    stopwords: HashSet<String>,
+    vocabulary: Vec<String>,
+    index: HashMap<String, usize>,
+    idf: Vec<f32>,
+}
+
+impl BagOfWords {
+    fn new(weighting: Weighting, options: &Options) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            weighting,
+            stopwords: options.stopwords.load()?,
+            vocabulary: Vec::new(),
+            index: HashMap::new(),
+            idf: Vec::new(),
+        })
+    }
+
+    fn terms(&self, tokens: &[String]) -> Vec<String> {
+        content_words(tokens, &self.stopwords)
+    }
+}
+
+impl FeatureExtractor for BagOfWords {
+    fn name(&self) -> &'static str {
+        match self.weighting {
+            Weighting::Counts => "bow",
+            Weighting::TfIdf => "tfidf",
+        }
+    }
+
+    /// The vocabulary is every non-stopword in `definitions`, sorted.
+    fn fit(
+        &mut self,
+        documents: &[Document],
+        tokenizer: &Tokenizer,
+    ) -> Result<FitReport, Box<dyn Error>> {
+        self.stopwords = normalize_words(&self.stopwords, tokenizer);
+        let documents: Vec<Vec<String>> = documents
+            .iter()
+            .map(|document| self.terms(&document.tokens))
+            .collect();
+        let document_frequency = document_frequencies(&documents);
+
+        self.vocabulary = document_frequency.keys().cloned().collect();
+        self.index = self
+            .vocabulary
+            .iter()
+            .enumerate()
+            .map(|(index, term)| (term.clone(), index))
+            .collect();
+        self.idf = document_frequency
+            .values()
+            .map(|&df| idf(documents.len(), df))
+            .collect();
+        Ok(FitReport {
+            summary: Some(format!(
+                "Built a vocabulary of {} words from {} definitions",
+                self.vocabulary.len(),
+                documents.len()
+            )),
+            warnings: Vec::new(),
+        })
+    }
+
+    fn dimensions(&self) -> Vec<String> {
+        self.vocabulary.clone()
+    }
+
+    fn extract(&self, document: &Document) -> SparseVector {
+        let mut counts: BTreeMap<usize, f32> = BTreeMap::new();
+        for term in self.terms(&document.tokens) {
+            if let Some(&index) = self.index.get(&term) {
+                *counts.entry(index).or_default() += 1.0;
+            }
+        }
+
+        let entries = match self.weighting {
+            Weighting::Counts => counts.into_iter().collect(),
+            Weighting::TfIdf => {
+                let weighted: Vec<(usize, f32)> = counts
+                    .into_iter()
+                    .map(|(index, count)| (index, count * self.idf[index]))
+                    .collect();
+                let norm = weighted
+                    .iter()
+                    .map(|(_, value)| value * value)
+                    .sum::<f32>()
+                    .sqrt();
+                weighted
+                    .into_iter()
+                    .map(|(index, value)| (index, if norm > 0.0 { value / norm } else { 0.0 }))
+                    .collect()
+            }
+        };
+        SparseVector::from_entries(entries)
+    }
+}
+
+/// Skip-gram word vectors trained on every loaded definition; a definition
+/// is the mean of the vectors of its words.
+#[derive(Debug, Clone)]
+struct SkipGramFeatures {
+    config: SkipGramConfig,
+    stopwords: HashSet<String>,
+    model: SkipGram,
+}
+
+impl SkipGramFeatures {
+    fn new(options: &Options) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            config: SkipGramConfig {
+                dimensions: options.skipgram_dims,
+                epochs: options.skipgram_epochs,
+                seed: options.seed,
+                ..SkipGramConfig::default()
+            },
+            stopwords: options.stopwords.load()?,
+            model: SkipGram::default(),
+        })
+    }
+}
+
+impl FeatureExtractor for SkipGramFeatures {
+    fn name(&self) -> &'static str {
+        "skipgram"
+    }
+
+    fn fit(
+        &mut self,
+        documents: &[Document],
+        tokenizer: &Tokenizer,
+    ) -> Result<FitReport, Box<dyn Error>> {
+        self.stopwords = normalize_words(&self.stopwords, tokenizer);
+        let sentences: Vec<Vec<String>> = documents
+            .iter()
+            .map(|document| content_words(&document.tokens, &self.stopwords))
+            .collect();
+        self.model = SkipGram::train(&sentences, &self.config);
+        Ok(FitReport {
+            summary: Some(format!(
+                "Trained skip-gram vectors for {} words ({} dimensions, {} epochs)",
+                self.model.len(),
+                self.config.dimensions,
+                self.config.epochs
+            )),
+            warnings: Vec::new(),
+        })
+    }
+
+    fn dimensions(&self) -> Vec<String> {
+        (1..=self.config.dimensions)
+            .map(|dimension| dimension.to_string())
+            .collect()
+    }
+
+    fn extract(&self, document: &Document) -> SparseVector {
+        let mut sum = vec![0.0; self.config.dimensions];
+        let mut known = 0;
+        for word in content_words(&document.tokens, &self.stopwords) {
+            if let Some(vector) = self.model.vector(&word) {
+                for (slot, value) in sum.iter_mut().zip(vector) {
+                    *slot += value;
+                }
+                known += 1;
+            }
+        }
+        if known > 0 {
+            sum.iter_mut().for_each(|value| *value /= known as f32);
+        }
+        sum.into()
+    }
+}
+
+/// Pretrained word2vec/GloVe/fastText vectors from `--vectors`; a
+/// definition is the mean of the vectors of its words, optionally weighted
+/// by their inverse document frequency across the loaded definitions.
+#[derive(Debug)]
+struct PretrainedVectors {
+    path: PathBuf,
+    idf_weighted: bool,
+    stopwords: HashSet<String>,
+    vectors: WordVectors,
+    idf: HashMap<String, f32>,
+}
+
+impl PretrainedVectors {
+    fn new(options: &Options) -> Result<Self, Box<dyn Error>> {
+        let path = options
+            .vectors
+            .clone()
+            .ok_or("the vectors extractor needs --vectors <PATH>")?;
+        Ok(Self {
+            path,
+            idf_weighted: options.vectors_idf,
+            stopwords: options.stopwords.load()?,
+            vectors: WordVectors::default(),
+            idf: HashMap::new(),
+        })
+    }
+}
+
+impl FeatureExtractor for PretrainedVectors {
+    fn name(&self) -> &'static str {
+        "vectors"
+    }
+
+    /// Loads only the vectors of words that occur in some definition, each
+    /// filed under its normalized form.
+    fn fit(
+        &mut self,
+        documents: &[Document],
+        tokenizer: &Tokenizer,
+    ) -> Result<FitReport, Box<dyn Error>> {
+        self.stopwords = normalize_words(&self.stopwords, tokenizer);
+        let documents: Vec<Vec<String>> = documents
+            .iter()
+            .map(|document| content_words(&document.tokens, &self.stopwords))
+            .collect();
+        let document_frequency = document_frequencies(&documents);
+        self.vectors = WordVectors::load(&self.path, |word| {
+            Some(tokenizer.normalize(word)).filter(|key| document_frequency.contains_key(key))
+        })?;
+        if self.idf_weighted {
+            self.idf = document_frequency
+                .iter()
+                .map(|(word, &df)| (word.clone(), idf(documents.len(), df)))
+                .collect();
+        }
+
+        Ok(FitReport {
+            summary: Some(format!(
+                "Loaded {}-dimensional vectors for {} of {} definition words from {} ({} lines, {} malformed lines skipped)",
+                self.vectors.dimensions(),
+                self.vectors.len(),
+                document_frequency.len(),
+                self.path.display(),
+                self.vectors.lines,
+                self.vectors.issues.len()
+            )),
+            warnings: self
+                .vectors
+                .issues
+                .iter()
+                .map(|issue| format!("{}:{}: {}", self.path.display(), issue.line, issue.message))
+                .collect(),
+        })
+    }
+
+    fn dimensions(&self) -> Vec<String> {
+        (1..=self.vectors.dimensions())
+            .map(|dimension| dimension.to_string())
+            .collect()
+    }
+
+    fn extract(&self, document: &Document) -> SparseVector {
+        let mut sum = vec![0.0; self.vectors.dimensions()];
+        let mut total = 0.0;
+        for word in content_words(&document.tokens, &self.stopwords) {
+            let Some(vector) = self.vectors.get(&word) else {
+                continue;
+            };
+            let weight = if self.idf_weighted {
+                self.idf.get(&word).copied().unwrap_or(0.0)
+            } else {
+                1.0
+            };
+            for (slot, value) in sum.iter_mut().zip(vector) {
+                *slot += weight * value;
+            }
+            total += weight;
+        }
+        if total > 0.0 {
+            sum.iter_mut().for_each(|value| *value /= total);
+        }
+        sum.into()
+    }
+}
+
+/// One dimension per category of the `--lexicon` files (or of the builtin
+/// sensory/abstract lexicon when none are given): the weighted share of the
+/// definition's words that belong to it.
+#[derive(Debug, Clone)]
+struct LexiconFeatures {
+    paths: Vec<PathBuf>,
+    lexicon: Lexicon,
+}
+
+impl LexiconFeatures {
+    fn new(options: &Options) -> Self {
+        Self {
+            paths: options.lexicons.clone(),
+            lexicon: Lexicon::builtin(),
+        }
+    }
+}
+
+impl FeatureExtractor for LexiconFeatures {
+    fn name(&self) -> &'static str {
+        "lexicon"
+    }
+
+    fn fit(
+        &mut self,
+        documents: &[Document],
+        tokenizer: &Tokenizer,
+    ) -> Result<FitReport, Box<dyn Error>> {
+        if self.paths.is_empty() {
+            self.lexicon.normalize(|word| tokenizer.normalize(word));
+            self.lexicon.add_lemmas(documents, tokenizer);
+            return Ok(FitReport::default());
+        }
+        self.lexicon = Lexicon::default();
+        let mut warnings = Vec::new();
+        for path in &self.paths {
+            for issue in self.lexicon.load(path)? {
+                warnings.push(format!(
+                    "{}:{}: {}",
+                    path.display(),
+                    issue.line,
+                    issue.message
+                ));
+            }
+        }
+        let words = self.lexicon.words();
+        self.lexicon.normalize(|word| tokenizer.normalize(word));
+        self.lexicon.add_lemmas(documents, tokenizer);
+        Ok(FitReport {
+            summary: Some(format!(
+                "Loaded {} words in {} categories from {} lexicon files ({} malformed lines skipped)",
+                words,
+                self.lexicon.categories().len(),
+                self.paths.len(),
+                warnings.len()
+            )),
+            warnings,
+        })
+    }
+
+    fn dimensions(&self) -> Vec<String> {
+        self.lexicon.categories().to_vec()
+    }
+
+    fn extract(&self, document: &Document) -> SparseVector {
+        self.lexicon.scores(&document.tokens).into()
+    }
+}
+
+/// Part-of-speech shares of the definition's words, from the rule-based
+/// tagger, and a one-hot guess at the headword's own part of speech: the
+/// dictionary's label when it names an open class, otherwise the one the
+/// gloss's shape implies. Averaged per morpheme, the headword dimensions
+/// say how often a morpheme appears in noun, verb, adjective or adverb
+/// entries.
+#[derive(Debug, Clone, Copy)]
+struct PartOfSpeech;
+
+impl FeatureExtractor for PartOfSpeech {
+    fn name(&self) -> &'static str {
+        "pos"
+    }
+
+    fn dimensions(&self) -> Vec<String> {
+        PosTag::ALL
+            .iter()
+            .map(|tag| format!("{tag}_ratio"))
+            .chain(PosTag::OPEN.iter().map(|tag| format!("headword_{tag}")))
+            .collect()
+    }
+
+    /// Tags the words as written, since normalized tokens may be stems.
+    fn extract(&self, document: &Document) -> SparseVector {
+        let words = document.words();
+        let tags = pos::tag(&words);
+        let mut dense = vec![0.0; PosTag::ALL.len() + PosTag::OPEN.len()];
+        for tag in &tags {
+            dense[tag.index()] += 1.0 / words.len() as f32;
+        }
+        let headword = document
+            .pos
+            .and_then(PosTag::from_dictionary_label)
+            .or_else(|| pos::headword_pos(&words, &tags));
+        if let Some(headword) = headword.filter(|tag| PosTag::OPEN.contains(tag)) {
+            dense[PosTag::ALL.len() + headword.index()] = 1.0;
+        }
+        dense.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A feature set built from command-line `args` and fitted on
+    /// `definitions`.
+    fn fitted(args: &[&str], definitions: &[&str]) -> (FeatureSet, Vec<DictionaryEntry>) {
+        let options = Options::parse(args.iter().map(|arg| arg.to_string())).expect("valid args");
+        let mut features =
+            FeatureSet::new(&options, &Rc::new(Syllabifier::builtin())).expect("features build");
+        let entries: Vec<DictionaryEntry> = definitions
+            .iter()
+            .map(|definition| DictionaryEntry::new(definition))
+            .collect();
+        features
+            .fit(&entries.iter().collect::<Vec<_>>())
+            .expect("features fit");
+        (features, entries)
+    }
+
+    #[test]
+    fn extractors_are_concatenated_at_their_fitted_widths() {
+        let (features, entries) = fitted(
+            &["--features", "bow,basic"],
+            &["the quality of being kind.", "not kind."],
+        );
+        let names = features.dimension_names();
+        assert_eq!(names[..3], ["bow.kind", "bow.quality", "basic.word_count"]);
+        assert_eq!(names.len(), 2 + 5);
+
+        // "not kind.": one "kind", then two words, the first of `basic`.
+        let vector = features.extract(&entries[1]);
+        assert_eq!(vector.entries()[..2], [(0, 1.0), (2, 2.0)]);
+    }
+
+    #[test]
+    fn bags_of_words_count_vocabulary_words_only() {
+        let definitions = ["kind words, kind deeds.", "the cruel words."];
+        let (features, entries) = fitted(&["--features", "bow"], &definitions);
+        assert_eq!(
+            features.dimension_names(),
+            ["bow.cruel", "bow.deeds", "bow.kind", "bow.words"]
+        );
+        let vector = features.extract(&entries[0]);
+        assert_eq!(vector.entries(), [(1, 1.0), (2, 2.0), (3, 1.0)]);
+        // Words outside the fitted vocabulary are dropped.
+        let unseen = DictionaryEntry::new("kind unicorns.");
+        assert_eq!(features.extract(&unseen).entries(), [(2, 1.0)]);
+    }
+
+    #[test]
+    fn tfidf_silences_shared_words_and_normalizes_rows() {
+        let definitions = ["kind words, kind deeds.", "the cruel words."];
+        let (features, entries) = fitted(&["--features", "tfidf"], &definitions);
+        // "words" is in both definitions; "kind" (twice) and "deeds" share
+        // the idf ln(3/2), so the row is (2, 1) over its length.
+        let vector = features.extract(&entries[0]);
+        let expected = [(1, 1.0 / 5f32.sqrt()), (2, 2.0 / 5f32.sqrt())];
+        assert_eq!(vector.entries().len(), expected.len());
+        for (&(index, value), (want_index, want)) in vector.entries().iter().zip(expected) {
+            assert_eq!(index, want_index);
+            assert!((value - want).abs() < 1e-6, "{vector:?}");
+        }
+        assert_eq!(features.extract(&entries[1]).entries(), [(0, 1.0)]);
+    }
+
+    #[test]
+    fn readability_scores_words_sentences_and_complex_words() {
+        // Nine words in two sentences, 16 syllables; "quality" and
+        // "considerate" are complex.
+        let definition = "the quality of being kind. a considerate nature beyond";
+        let (features, entries) = fitted(&["--features", "readability"], &[definition]);
+        let values = features.extract(&entries[0]).to_dense(4);
+        let expected = [
+            16.0 / 9.0,
+            4.5,
+            0.39 * 4.5 + 11.8 * 16.0 / 9.0 - 15.59,
+            0.4 * (4.5 + 100.0 * 2.0 / 9.0),
+        ];
+        for (value, want) in values.iter().zip(expected) {
+            assert!((value - want).abs() < 1e-4, "{values:?}");
+        }
+    }
+
+    #[test]
+    fn inflectional_endings_do_not_make_words_complex() {
+        let readability = Readability::new(&Rc::new(Syllabifier::builtin()));
+        assert!(readability.is_complex("quality"));
+        // "beginning" has three syllables only because of its "-ing".
+        assert_eq!(readability.syllabifier.count("beginning"), 3);
+        assert!(!readability.is_complex("beginning"));
+        assert!(readability.is_complex("considering"));
+    }
+
+    #[test]
+    fn part_of_speech_ratios_and_headword_class() {
+        let (features, entries) = fitted(&["--features", "pos"], &["to depart abruptly."]);
+        let third = 1.0 / 3.0;
+        // to(preposition) depart(verb) abruptly(adverb), defining a verb.
+        assert_eq!(
+            features.extract(&entries[0]).entries(),
+            [
+                (1, third),
+                (3, third),
+                (6, third),
+                (PosTag::ALL.len() + 1, 1.0)
+            ]
+        );
+
+        // A dictionary label wins over the shape of the gloss.
+        let mut labelled = DictionaryEntry::new("a lack of kindness.");
+        labelled.pos = Some("adj".to_string());
+        let vector = features.extract(&labelled);
+        let headword: Vec<usize> = vector
+            .entries()
+            .iter()
+            .map(|&(index, _)| index)
+            .filter(|&index| index >= PosTag::ALL.len())
+            .collect();
+        assert_eq!(headword, [PosTag::ALL.len() + PosTag::Adjective.index()]);
+    }
+
+    #[test]
+    fn sparse_vectors_keep_only_sorted_non_zero_entries() {
+        let vector = SparseVector::from_entries(vec![(3, 2.0), (0, 0.0), (1, -1.0)]);
+        assert_eq!(vector.entries(), [(1, -1.0), (3, 2.0)]);
+        assert_eq!(vector.to_dense(4), [0.0, -1.0, 0.0, 2.0]);
+        assert_eq!(SparseVector::from(vec![0.0, -1.0, 0.0, 2.0]), vector);
+    }
+
+    #[test]
+    fn unknown_extractors_list_the_registry() {
+        let Err(err) = lookup("telepathy") else {
+            panic!("telepathy is not registered");
+        };
+        assert!(
+            err.starts_with("unknown feature extractor 'telepathy' (expected one of 'basic', ")
+        );
+        assert!(err.ends_with("'vectors')"), "{err}");
+    }
+}
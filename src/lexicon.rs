@@ -0,0 +1,206 @@
+//! Semantic lexicons: words grouped into named categories ("sensory",
+//! "abstract", "emotion", ...), each category becoming one feature
+//! dimension. Under `--normalize lemma` words are matched on their lemma,
+//! so "sounds" and "glowing" count towards the entries "sound" and "glow".
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::dictionary::LineIssue;
+use crate::read_words_from_file;
+use crate::tokenizer::{Document, Tokenizer};
+
+/// The categories behind the `basic` extractor's sensory and abstract
+/// ratios.
+const BUILTIN_CATEGORIES: [(&str, &[&str]); 2] = [
+    (
+        "sensory",
+        &[
+            "light", "bright", "glow", "sound", "tone", "taste", "touch", "smell", "colour",
+            "color", "hear", "see",
+        ],
+    ),
+    (
+        "abstract",
+        &[
+            "state",
+            "quality",
+            "ability",
+            "capacity",
+            "process",
+            "condition",
+            "power",
+            "toughness",
+            "recovery",
+            "change",
+            "time",
+            "action",
+        ],
+    ),
+];
+
+/// Words mapped to weighted categories.
+#[derive(Debug, Clone, Default)]
+pub struct Lexicon {
+    categories: Vec<String>,
+    /// Word → (category index, weight).
+    entries: HashMap<String, Vec<(usize, f32)>>,
+}
+
+impl Lexicon {
+    /// The sensory and abstract categories.
+    pub fn builtin() -> Self {
+        let mut lexicon = Self::default();
+        for (category, words) in BUILTIN_CATEGORIES {
+            for word in words {
+                lexicon.insert(word, category, 1.0);
+            }
+        }
+        lexicon
+    }
+
+    /// Merges a lexicon file. A `.tsv` file holds `word<TAB>category` lines
+    /// with an optional third `weight` column (default 1); any other file is
+    /// a word list, one word per line, whose category is the file name
+    /// without its extension. Blank lines and `#` comments are skipped;
+    /// malformed lines are returned and skipped.
+    pub fn load(&mut self, path: &Path) -> Result<Vec<LineIssue>, Box<dyn Error>> {
+        let is_tsv = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("tsv"));
+        if !is_tsv {
+            let category = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .filter(|stem| !stem.is_empty())
+                .ok_or_else(|| format!("cannot name a category after {}", path.display()))?;
+            let words = read_words_from_file(path)
+                .map_err(|err| format!("cannot read lexicon {}: {err}", path.display()))?;
+            for word in words {
+                self.insert(&word, &category, 1.0);
+            }
+            return Ok(Vec::new());
+        }
+
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read lexicon {}: {err}", path.display()))?;
+        let mut issues = Vec::new();
+        for (index, line) in contents.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+            let (word, category, weight) = match fields[..] {
+                [word, category] => (word, category, Ok(1.0)),
+                [word, category, weight] => (word, category, weight.parse::<f32>()),
+                _ => {
+                    issues.push(LineIssue::new(
+                        line_number,
+                        "expected word<TAB>category[<TAB>weight]",
+                    ));
+                    continue;
+                }
+            };
+            let Ok(weight) = weight else {
+                issues.push(LineIssue::new(line_number, "weight is not a number"));
+                continue;
+            };
+            if word.is_empty() || category.is_empty() {
+                issues.push(LineIssue::new(line_number, "empty word or category"));
+                continue;
+            }
+            self.insert(word, category, weight);
+        }
+        Ok(issues)
+    }
+
+    /// Adds `word` to `category`, replacing any earlier weight it had there.
+    pub fn insert(&mut self, word: &str, category: &str, weight: f32) {
+        let category = match self.categories.iter().position(|known| known == category) {
+            Some(index) => index,
+            None => {
+                self.categories.push(category.to_string());
+                self.categories.len() - 1
+            }
+        };
+        self.add(word.to_lowercase(), category, weight);
+    }
+
+    fn add(&mut self, word: String, category: usize, weight: f32) {
+        let memberships = self.entries.entry(word).or_default();
+        match memberships
+            .iter_mut()
+            .find(|(existing, _)| *existing == category)
+        {
+            Some(membership) => membership.1 = weight,
+            None => memberships.push((category, weight)),
+        }
+    }
+
+    /// Passes every word through `normalize`, so that the lexicon matches
+    /// tokens normalized the same way. Words that collapse together keep
+    /// the weight of whichever is seen last.
+    pub fn normalize(&mut self, normalize: impl Fn(&str) -> String) {
+        let mut words: Vec<_> = std::mem::take(&mut self.entries).into_iter().collect();
+        words.sort_by(|a, b| a.0.cmp(&b.0));
+        for (word, memberships) in words {
+            for (category, weight) in memberships {
+                self.add(normalize(&word), category, weight);
+            }
+        }
+    }
+
+    /// Under `--normalize lemma`, files every token of `documents` that the
+    /// lexicon lacks under its lemma's memberships, so "glowing" counts
+    /// towards "glow" even when no definition uses "glow" itself.
+    pub fn add_lemmas(&mut self, documents: &[Document], tokenizer: &Tokenizer) {
+        if !tokenizer.lemmatizes() {
+            return;
+        }
+        let mut inflected = Vec::new();
+        for token in documents.iter().flat_map(|document| &document.tokens) {
+            if self.entries.contains_key(token) {
+                continue;
+            }
+            let lemma = tokenizer.lemma_among(token, |word| self.entries.contains_key(word));
+            if let Some(memberships) = self.entries.get(&lemma) {
+                inflected.push((token.clone(), memberships.clone()));
+            }
+        }
+        self.entries.extend(inflected);
+    }
+
+    pub fn categories(&self) -> &[String] {
+        &self.categories
+    }
+
+    pub fn words(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Per category, the summed weight of the matching tokens divided by the
+    /// number of tokens.
+    pub fn scores(&self, tokens: &[String]) -> Vec<f32> {
+        let mut scores = vec![0.0; self.categories.len()];
+        if tokens.is_empty() {
+            return scores;
+        }
+        for token in tokens {
+            for &(category, weight) in self.lookup(token) {
+                scores[category] += weight;
+            }
+        }
+        for score in &mut scores {
+            *score /= tokens.len() as f32;
+        }
+        scores
+    }
+
+    fn lookup(&self, token: &str) -> &[(usize, f32)] {
+        self.entries.get(token).map_or(&[], Vec::as_slice)
+    }
+}
@@ -0,0 +1,240 @@
+//! Latent semantic analysis for `--dims`: a truncated SVD of the sparse
+//! morpheme-by-feature matrix, computed with the randomized range finder of
+//! Halko, Martinsson & Tropp (2011) so that only a handful of dense vectors
+//! the size of the vocabulary are ever materialised.
+
+use crate::features::SparseVector;
+use crate::rng::SplitMix64;
+
+/// Extra random directions sampled beyond `k`, which sharpens the estimate
+/// of the trailing components.
+const OVERSAMPLING: usize = 10;
+/// Power iterations; each one pushes the sample towards the dominant
+/// subspace when the spectrum decays slowly, as it does for term matrices.
+const POWER_ITERATIONS: usize = 2;
+/// Singular values below this (relative to the largest) are rank noise.
+const RANK_TOLERANCE: f64 = 1e-9;
+const JACOBI_SWEEPS: usize = 100;
+
+/// The leading right singular vectors of a matrix and their singular values,
+/// largest first.
+#[derive(Debug, Clone)]
+pub struct TruncatedSvd {
+    pub singular_values: Vec<f64>,
+    /// One unit vector per component, each `columns` long.
+    components: Vec<Vec<f64>>,
+    /// Squared Frobenius norm of the decomposed matrix.
+    total_energy: f64,
+}
+
+impl TruncatedSvd {
+    /// Decomposes the matrix whose rows are `rows` (each `columns` wide),
+    /// keeping at most `k` components; fewer are kept when the matrix has
+    /// lower rank.
+    pub fn fit(rows: &[SparseVector], columns: usize, k: usize, seed: u64) -> Self {
+        let total_energy = rows
+            .iter()
+            .flat_map(|row| row.entries())
+            .map(|&(_, value)| f64::from(value).powi(2))
+            .sum();
+        let sample = (k + OVERSAMPLING).min(rows.len()).min(columns);
+        if sample == 0 {
+            return Self {
+                singular_values: Vec::new(),
+                components: Vec::new(),
+                total_energy,
+            };
+        }
+
+        // Range finder: Q spans (approximately) the column space of A.
+        let mut rng = SplitMix64::new(seed);
+        let omega: Vec<Vec<f64>> = (0..sample)
+            .map(|_| (0..columns).map(|_| 2.0 * rng.uniform() - 1.0).collect())
+            .collect();
+        let mut q = orthonormalize(multiply(rows, &omega));
+        for _ in 0..POWER_ITERATIONS {
+            let z = orthonormalize(multiply_transposed(rows, columns, &q));
+            q = orthonormalize(multiply(rows, &z));
+        }
+
+        // B = Qᵀ A is small (sample × columns); its right singular vectors
+        // are A's. They come from the eigenvectors W of B Bᵀ: v = Bᵀ w / σ.
+        let b = multiply_transposed(rows, columns, &q);
+        let gram: Vec<Vec<f64>> = b
+            .iter()
+            .map(|left| b.iter().map(|right| dot(left, right)).collect())
+            .collect();
+        let (eigenvalues, eigenvectors) = symmetric_eigen(gram);
+
+        let mut order: Vec<usize> = (0..eigenvalues.len()).collect();
+        order.sort_by(|&a, &b| eigenvalues[b].total_cmp(&eigenvalues[a]));
+        let largest = eigenvalues[order[0]].max(0.0).sqrt();
+
+        let mut singular_values = Vec::new();
+        let mut components = Vec::new();
+        for index in order.into_iter().take(k) {
+            let sigma = eigenvalues[index].max(0.0).sqrt();
+            if sigma <= largest * RANK_TOLERANCE {
+                break;
+            }
+            let mut component = vec![0.0; columns];
+            for (row, weight) in b.iter().zip(&eigenvectors[index]) {
+                for (slot, value) in component.iter_mut().zip(row) {
+                    *slot += value * weight / sigma;
+                }
+            }
+            // Fix the sign so that reruns and reorderings agree.
+            let pivot = component
+                .iter()
+                .copied()
+                .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+                .unwrap_or(0.0);
+            if pivot < 0.0 {
+                component.iter_mut().for_each(|value| *value = -*value);
+            }
+            singular_values.push(sigma);
+            components.push(component);
+        }
+
+        Self {
+            singular_values,
+            components,
+            total_energy,
+        }
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.components.len()
+    }
+
+    /// Share of the matrix's squared norm captured by the kept components.
+    pub fn explained_variance(&self) -> f64 {
+        if self.total_energy <= 0.0 {
+            return 0.0;
+        }
+        self.singular_values
+            .iter()
+            .map(|sigma| sigma * sigma)
+            .sum::<f64>()
+            / self.total_energy
+    }
+
+    /// Coordinates of `row` in the latent space (its row of U Σ when it was
+    /// part of the fitted matrix).
+    pub fn project(&self, row: &SparseVector) -> Vec<f32> {
+        self.components
+            .iter()
+            .map(|component| {
+                row.entries()
+                    .iter()
+                    .map(|&(index, value)| f64::from(value) * component[index])
+                    .sum::<f64>() as f32
+            })
+            .collect()
+    }
+}
+
+/// A Ω for sparse A and Ω given as a list of columns; returns columns.
+fn multiply(rows: &[SparseVector], columns: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    columns
+        .iter()
+        .map(|column| {
+            rows.iter()
+                .map(|row| {
+                    row.entries()
+                        .iter()
+                        .map(|&(index, value)| f64::from(value) * column[index])
+                        .sum()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Aᵀ Q for sparse A and Q given as a list of columns; returns columns.
+fn multiply_transposed(rows: &[SparseVector], width: usize, q: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    q.iter()
+        .map(|column| {
+            let mut product = vec![0.0; width];
+            for (row, weight) in rows.iter().zip(column) {
+                for &(index, value) in row.entries() {
+                    product[index] += f64::from(value) * weight;
+                }
+            }
+            product
+        })
+        .collect()
+}
+
+/// Modified Gram–Schmidt. Columns that are (numerically) dependent on the
+/// earlier ones are zeroed rather than dropped, so shapes stay fixed.
+fn orthonormalize(mut columns: Vec<Vec<f64>>) -> Vec<Vec<f64>> {
+    for index in 0..columns.len() {
+        let (done, rest) = columns.split_at_mut(index);
+        let column = &mut rest[0];
+        for basis in done.iter() {
+            let projection = dot(column, basis);
+            for (value, base) in column.iter_mut().zip(basis) {
+                *value -= projection * base;
+            }
+        }
+        let norm = dot(column, column).sqrt();
+        for value in column.iter_mut() {
+            *value = if norm > 1e-12 { *value / norm } else { 0.0 };
+        }
+    }
+    columns
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Cyclic Jacobi eigendecomposition of a small symmetric matrix. Returns the
+/// eigenvalues and, for each, its unit eigenvector.
+fn symmetric_eigen(mut a: Vec<Vec<f64>>) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let n = a.len();
+    // v[i] is the i-th eigenvector once the sweeps converge.
+    let mut v: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect();
+
+    for _ in 0..JACOBI_SWEEPS {
+        let off_diagonal: f64 = (0..n)
+            .flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i][j] * a[i][j])
+            .sum();
+        if off_diagonal < 1e-22 {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p][q].abs() < 1e-300 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for row in a.iter_mut() {
+                    let (kp, kq) = (row[p], row[q]);
+                    row[p] = c * kp - s * kq;
+                    row[q] = s * kp + c * kq;
+                }
+                rotate_rows(&mut a, p, q, c, s);
+                rotate_rows(&mut v, p, q, c, s);
+            }
+        }
+    }
+
+    ((0..n).map(|i| a[i][i]).collect(), v)
+}
+
+/// Replaces rows `p` and `q` of `m` with their Givens rotation by (c, s).
+fn rotate_rows(m: &mut [Vec<f64>], p: usize, q: usize, c: f64, s: f64) {
+    let (row_p, row_q) = (m[p].clone(), m[q].clone());
+    for (k, (x, y)) in row_p.iter().zip(&row_q).enumerate() {
+        m[p][k] = c * x - s * y;
+        m[q][k] = s * x + c * y;
+    }
+}
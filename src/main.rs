@@ -1,123 +1,82 @@
+mod cli;
+mod compound;
+mod derivation;
+mod dictionary;
+mod eval;
+mod features;
+mod json;
+mod lexicon;
+mod lsa;
+mod morfessor;
+mod morphology;
+mod orthography;
+mod pos;
+mod rng;
+mod scaling;
+mod skipgram;
+mod stemmer;
+mod subword;
+mod syllables;
+mod tokenizer;
+mod toml;
+mod vectors;
+mod viterbi;
+
 use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
 use std::fs;
 use std::path::Path;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use cli::{DictionarySource, Options};
+use compound::{CompoundSplitter, CompoundTree};
+use derivation::{DerivationStyle, DerivationTree};
+use dictionary::{Dictionary, DictionaryEntry};
+use features::{FeatureSet, SparseVector};
+use lsa::TruncatedSvd;
+use morfessor::MorfessorModel;
+use morphology::MorphologyProfile;
+use scaling::Scaler;
+use subword::{BpeModel, UnigramModel};
+use syllables::Syllabifier;
 
-const DICTIONARY_ENTRIES: [(&str, &str); 9] = [
-    (
-        "antidisestablishmentarianism",
-        "Oxford English Dictionary (paraphrased): opposition to the withdrawal of state support or recognition from an established church.",
-    ),
-    (
-        "hypermetamorphosis",
-        "Oxford English Dictionary (paraphrased): a kind of insect development marked by distinctly different successive larval stages.",
-    ),
-    (
-        "biblioklept",
-        "Oxford English Dictionary (paraphrased): a person who steals books; a book thief.",
-    ),
-    (
-        "defenestration",
-        "Oxford English Dictionary (paraphrased): the act of throwing someone or something out of a window.",
-    ),
-    (
-        "absquatulate",
-        "Oxford English Dictionary (paraphrased): to depart abruptly; to abscond with comic haste.",
-    ),
-    (
-        "cattywampus",
-        "Oxford English Dictionary (paraphrased): askew or awry; positioned diagonally in a delightfully unruly fashion.",
-    ),
-    (
-        "transmogrification",
-        "Oxford English Dictionary (paraphrased): a transformation, especially one that is startling or magical.",
-    ),
-    (
-        "sesquipedalian",
-        "Oxford English Dictionary (paraphrased): characterized by or fond of using long words.",
-    ),
-    (
-        "kerfuffle",
-        "Oxford English Dictionary (paraphrased): a commotion or fuss, especially one caused by conflicting opinions.",
-    ),
-];
-
-const PREFIXES: [&str; 11] = [
-    "hyper", "trans", "inter", "sesqui", "anti", "ab", "de", "dis", "ultra", "pseudo", "super",
-];
-
-const SUFFIXES: [&str; 17] = [
-    "arianism",
-    "ification",
-    "mogrification",
-    "ulation",
-    "arian",
-    "ation",
-    "mentation",
-    "iasis",
-    "iosis",
-    "osis",
-    "ulate",
-    "icism",
-    "ism",
-    "ious",
-    "ian",
-    "ness",
-    "ment",
-];
-
-const ROOT_PATTERNS: [&str; 21] = [
-    "antidisestablish",
-    "establish",
-    "transmogr",
-    "cattywampus",
-    "sesquiped",
-    "biblio",
-    "bibli",
-    "hyper",
-    "mogr",
-    "meta",
-    "morph",
-    "klept",
-    "fenestr",
-    "squat",
-    "wampus",
-    "pedal",
-    "ped",
-    "kerfuffle",
-    "fic",
-    "ruffle",
-    "fuffle",
-];
-
-const SENSORY_KEYWORDS: [&str; 12] = [
-    "light", "bright", "glow", "sound", "tone", "taste", "touch", "smell", "colour", "color",
-    "hear", "see",
-];
-const ABSTRACT_KEYWORDS: [&str; 12] = [
-    "state",
-    "quality",
-    "ability",
-    "capacity",
-    "process",
-    "condition",
-    "power",
-    "toughness",
-    "recovery",
-    "change",
-    "time",
-    "action",
-];
+/// Matrices wider than this print only each row's non-zero entries, by name.
+const DENSE_COLUMNS: usize = 16;
+/// Standard normal quantile for the 95% confidence intervals of `--stats`.
+const CONFIDENCE_Z: f32 = 1.96;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = std::env::args().collect();
-    let dictionary = build_dictionary();
+    let options = Options::parse(std::env::args().skip(1))?;
+    if options.show_help {
+        print!("{}", cli::USAGE);
+        return Ok(());
+    }
+
+    let dictionary = load_dictionary(&options)?;
+    let profile = match &options.morphology {
+        Some(path) => {
+            let profile = MorphologyProfile::load(path)?;
+            println!(
+                "Loaded morphology profile '{}' ({} prefixes, {} suffixes, {} roots)",
+                profile.name,
+                profile.inventory(MorphemeKind::Prefix).len(),
+                profile.inventory(MorphemeKind::Suffix).len(),
+                profile.inventory(MorphemeKind::Root).len()
+            );
+            profile
+        }
+        None => MorphologyProfile::builtin(),
+    };
 
-    let words = if let Some(path) = args.get(1) {
-        let expanded = expand_tilde(path);
-        read_words_from_file(Path::new(&expanded))?
+    if let Some(path) = &options.eval {
+        return evaluate(path, &options, &dictionary, &profile);
+    }
+
+    let words = if let Some(path) = &options.word_list {
+        read_words_from_file(path)?
     } else {
-        dictionary.keys().map(|word| (*word).to_string()).collect()
+        dictionary.headwords().map(str::to_string).collect()
     };
 
     if words.is_empty() {
@@ -127,87 +86,569 @@ fn main() -> Result<(), Box<dyn Error>> {
         return Ok(());
     }
 
+    let syllabifier = Rc::new(load_syllabifier(&options)?);
+    let mut features = FeatureSet::new(&options, &syllabifier)?;
+    let entries: Vec<&DictionaryEntry> = dictionary.senses().collect();
+    for (registration, report) in options.features.iter().zip(features.fit(&entries)?) {
+        for warning in &report.warnings {
+            eprintln!("warning: {warning}");
+        }
+        if let Some(summary) = report.summary {
+            println!("{}: {summary}", registration.name);
+        }
+    }
+    if let Some(scaler) = build_scaler(&options, &features, &entries)? {
+        features.set_scaler(scaler);
+    }
+    let mut runs = Vec::new();
+    for &kind in &options.segmenters {
+        let segmenter = build_segmenter(kind, &options, &words)?;
+        if options.segmenters.len() > 1 {
+            println!();
+            println!("=== {} segmenter ===", kind.label());
+        }
+        let Some(summary) = embed_words(
+            &segmenter,
+            &words,
+            &dictionary,
+            &profile,
+            &features,
+            &options,
+        ) else {
+            println!(
+                "No dictionary entries matched the provided words. Try using the bundled examples."
+            );
+            return Ok(());
+        };
+        runs.push((kind, summary));
+    }
+
+    if runs.len() > 1 {
+        println!();
+        println!("Segmenter comparison:");
+        for (kind, summary) in runs {
+            println!(
+                "  {:<10} {} morpheme types, {:.2} morphemes per word",
+                kind.label(),
+                summary.morpheme_types,
+                summary.morphemes_per_word()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// The syllabifier shared by `basic` and `readability`, with `--cmudict`
+/// merged in once and its malformed lines reported here.
+fn load_syllabifier(options: &Options) -> Result<Syllabifier, Box<dyn Error>> {
+    let mut syllabifier = Syllabifier::builtin();
+    let Some(path) = &options.cmudict else {
+        return Ok(syllabifier);
+    };
+    let issues = syllabifier.load_cmudict(path)?;
+    for issue in &issues {
+        eprintln!(
+            "warning: {}:{}: {}",
+            path.display(),
+            issue.line,
+            issue.message
+        );
+    }
+    println!(
+        "Loaded syllable counts from {} ({} malformed lines skipped)",
+        path.display(),
+        issues.len()
+    );
+    Ok(syllabifier)
+}
+
+/// `--eval`: scores every requested segmenter against a gold file instead of
+/// building embeddings.
+fn evaluate(
+    path: &Path,
+    options: &Options,
+    dictionary: &Dictionary,
+    profile: &MorphologyProfile,
+) -> Result<(), Box<dyn Error>> {
+    let gold = eval::GoldStandard::load(path)?;
+    for issue in &gold.issues {
+        eprintln!(
+            "warning: {}:{}: {}",
+            path.display(),
+            issue.line,
+            issue.message
+        );
+    }
+    println!(
+        "Loaded {} gold words from {} ({} malformed lines skipped)",
+        gold.entries.len(),
+        path.display(),
+        gold.issues.len()
+    );
+    if gold.entries.is_empty() {
+        return Ok(());
+    }
+    let words: Vec<String> = gold
+        .entries
+        .iter()
+        .map(|entry| entry.word.clone())
+        .collect();
+
+    let splitter = options
+        .compounds
+        .then(|| CompoundSplitter::new(profile.linkers()));
+
+    let mut runs = Vec::new();
+    for &kind in &options.segmenters {
+        let segmenter = build_segmenter(kind, options, &words)?;
+        let mut evaluation = eval::Evaluation::default();
+        for entry in &gold.entries {
+            let (segmentations, _) = analyse_word(
+                &entry.word,
+                &segmenter,
+                splitter.as_ref(),
+                dictionary,
+                profile,
+                1,
+            );
+            let predicted = segmentations
+                .into_iter()
+                .next()
+                .map(|best| best.morphemes)
+                .unwrap_or_default();
+            let surfaces: Vec<&str> = predicted
+                .iter()
+                .map(|morpheme| morpheme.surface.as_str())
+                .collect();
+            evaluation.add(entry, &surfaces);
+        }
+
+        println!();
+        if options.segmenters.len() > 1 {
+            println!("=== {} segmenter ===", kind.label());
+        }
+        println!(
+            "Boundary precision {:.3}, recall {:.3}, F1 {:.3} ({} hits, {} predicted, {} gold boundaries)",
+            evaluation.precision(),
+            evaluation.recall(),
+            evaluation.f1(),
+            evaluation.hits,
+            evaluation.predicted,
+            evaluation.gold
+        );
+        println!(
+            "Exact match: {} of {} words ({:.1}%)",
+            evaluation.exact,
+            evaluation.words,
+            100.0 * evaluation.exact_rate()
+        );
+        if !evaluation.mistakes.is_empty() {
+            println!("Mistakes ({}):", evaluation.mistakes.len());
+            for mistake in &evaluation.mistakes {
+                println!(
+                    "  {}: predicted {}, gold {} ({} spurious, {} missed)",
+                    mistake.word,
+                    eval::mark(&mistake.word, &mistake.predicted),
+                    eval::mark(&mistake.word, &mistake.gold),
+                    mistake.spurious(),
+                    mistake.missed()
+                );
+            }
+        }
+        runs.push((kind, evaluation));
+    }
+
+    if runs.len() > 1 {
+        println!();
+        println!("Segmenter comparison:");
+        for (kind, evaluation) in runs {
+            println!(
+                "  {:<10} P {:.3}  R {:.3}  F1 {:.3}  exact {:.1}%",
+                kind.label(),
+                evaluation.precision(),
+                evaluation.recall(),
+                evaluation.f1(),
+                100.0 * evaluation.exact_rate()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Segments `word`, preferring a split into free dictionary headwords when
+/// compound splitting is on.
+fn analyse_word(
+    word: &str,
+    segmenter: &Segmenter,
+    splitter: Option<&CompoundSplitter>,
+    dictionary: &Dictionary,
+    profile: &MorphologyProfile,
+    n: usize,
+) -> (Vec<ScoredSegmentation>, Option<CompoundTree>) {
+    let compound = splitter.and_then(|splitter| {
+        splitter.split(&clean_word(word), |part| dictionary.get(part).is_some())
+    });
+    let segmentations = match &compound {
+        Some(tree) => ScoredSegmentation::single(tree.morphemes()),
+        None => segmenter.nbest(word, profile, n),
+    };
+    (segmentations, compound)
+}
+
+/// Totals from one pass of the embedding pipeline, for comparing segmenters.
+#[derive(Debug, Clone, Copy)]
+struct RunSummary {
+    words: usize,
+    morpheme_tokens: usize,
+    morpheme_types: usize,
+}
+
+impl RunSummary {
+    fn morphemes_per_word(&self) -> f32 {
+        self.morpheme_tokens as f32 / self.words.max(1) as f32
+    }
+}
+
+/// Segments every word with `segmenter`, prints the breakdowns and the
+/// resulting morpheme embedding matrix. Returns `None` when no word had a
+/// dictionary entry.
+fn embed_words(
+    segmenter: &Segmenter,
+    words: &[String],
+    dictionary: &Dictionary,
+    profile: &MorphologyProfile,
+    features: &FeatureSet,
+    options: &Options,
+) -> Option<RunSummary> {
     println!("Processing {} words...", words.len());
+    let splitter = options
+        .compounds
+        .then(|| CompoundSplitter::new(profile.linkers()));
 
     let mut embeddings: HashMap<MorphemeKey, EmbeddingAccumulator> = HashMap::new();
     let mut used_dictionary_entries = 0;
-    let mut dimensions = None;
+    let mut morpheme_tokens = 0;
+    let mut dimension_names = features.dimension_names();
 
-    for word in words {
+    for (word_index, word) in words.iter().enumerate() {
         let canonical = word.trim().to_lowercase();
         if canonical.is_empty() {
             continue;
         }
 
-        match dictionary.get(canonical.as_str()) {
-            Some(definition) => {
+        match dictionary.get(&canonical) {
+            Some(headword) => {
                 used_dictionary_entries += 1;
-                let morphemes = segment_into_morphemes(&canonical);
-                if morphemes.is_empty() {
+                let (mut segmentations, compound) = analyse_word(
+                    &canonical,
+                    segmenter,
+                    splitter.as_ref(),
+                    dictionary,
+                    profile,
+                    options.nbest,
+                );
+                for segmentation in &mut segmentations {
+                    orthography::canonicalize(
+                        &mut segmentation.morphemes,
+                        profile.alternations(),
+                        |kind, form| {
+                            profile.lookup(kind, form).is_some()
+                                || (kind == MorphemeKind::Root && dictionary.get(form).is_some())
+                        },
+                    );
+                }
+                let Some(morphemes) = segmentations
+                    .first()
+                    .map(|best| best.morphemes.as_slice())
+                    .filter(|morphemes| !morphemes.is_empty())
+                else {
                     println!("- {canonical}: no morphemic chunks produced by the segmenter");
                     continue;
-                }
+                };
 
-                let features = definition_to_features(definition);
-                dimensions.get_or_insert(features.len());
+                let sense_features: Vec<SparseVector> = headword
+                    .senses
+                    .iter()
+                    .map(|sense| features.extract(sense))
+                    .collect();
+                let observations = options.sense_mode.observations(sense_features);
+                if observations.is_empty() {
+                    continue;
+                }
 
-                let breakdown = morphemes
+                println!("- {canonical}: {}", breakdown(morphemes));
+                morpheme_tokens += morphemes.len();
+                let glosses = morphemes
                     .iter()
-                    .map(|morpheme| morpheme.display())
-                    .collect::<Vec<_>>()
-                    .join(" + ");
-
-                println!("- {canonical}: {breakdown}");
-                println!("  definition: {definition}");
-
-                for morpheme in &morphemes {
-                    let entry = embeddings
-                        .entry(MorphemeKey::from(morpheme))
-                        .or_insert_with(|| EmbeddingAccumulator::new(features.len()));
-                    entry.add(&features);
+                    .filter_map(|morpheme| {
+                        let spec = profile.lookup(morpheme.kind, &morpheme.underlying)?;
+                        let gloss = spec.gloss.as_deref()?;
+                        Some(match &spec.origin {
+                            Some(origin) => format!("{} = {gloss} [{origin}]", spec.text),
+                            None => format!("{} = {gloss}", spec.text),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                if !glosses.is_empty() {
+                    println!("  glosses: {}", glosses.join("; "));
+                }
+                if let Some(tree) = &compound {
+                    println!("  compound: {tree}");
+                }
+                let derivations: Vec<Option<DerivationTree>> = match options.derivation {
+                    Some(_) => segmentations
+                        .iter()
+                        .map(|segmentation| {
+                            DerivationTree::build(
+                                &segmentation.morphemes,
+                                compound.as_ref(),
+                                |form| dictionary.get(form).is_some(),
+                            )
+                        })
+                        .collect(),
+                    None => Vec::new(),
+                };
+                if let (Some(style), Some(Some(tree))) = (options.derivation, derivations.first()) {
+                    match style {
+                        DerivationStyle::Bracketed => println!("  derivation: {tree}"),
+                        DerivationStyle::Tree => {
+                            println!("  derivation:");
+                            print!("{}", tree.render("    "));
+                        }
+                    }
+                }
+                let spellings = morphemes
+                    .windows(2)
+                    .filter(|pair| pair[0].surface != pair[0].underlying)
+                    .map(|pair| {
+                        let rule = orthography::explain(&pair[0], &pair[1], profile.alternations())
+                            .map_or("alternation", |rule| rule.name.as_str());
+                        format!("{}→{} ({rule})", pair[0].surface, pair[0].underlying)
+                    })
+                    .collect::<Vec<_>>();
+                if !spellings.is_empty() {
+                    println!("  spelling: {}", spellings.join("; "));
+                }
+                if let Some(gold) = &headword.segmentation {
+                    println!("  gold: {}", breakdown(gold));
+                }
+                if options.nbest > 1 {
+                    println!("  n-best:");
+                    for (rank, segmentation) in segmentations.iter().enumerate() {
+                        println!(
+                            "    {}. p={:.3} cost={:.3}: {}",
+                            rank + 1,
+                            segmentation.probability,
+                            segmentation.cost,
+                            breakdown(&segmentation.morphemes)
+                        );
+                    }
+                }
+                let numbered = headword.senses.len() > 1;
+                for (index, sense) in headword.senses.iter().enumerate() {
+                    let label = if numbered {
+                        format!("sense {}", index + 1)
+                    } else {
+                        "definition".to_string()
+                    };
+                    match &sense.pos {
+                        Some(pos) => println!("  {label} ({pos}): {}", sense.definition),
+                        None => println!("  {label}: {}", sense.definition),
+                    }
+                    if let Some(attribution) = sense.attribution() {
+                        if numbered {
+                            println!("  {label} source: {attribution}");
+                        } else {
+                            println!("  source: {attribution}");
+                        }
+                    }
+                }
+
+                // Each analysis contributes in proportion to its confidence;
+                // with a single segmentation this is a plain unweighted add.
+                // Intermediate stems of its derivation tree share the word's
+                // observations just like its leaf morphemes.
+                for (index, segmentation) in segmentations.iter().enumerate() {
+                    let stems = derivations
+                        .get(index)
+                        .and_then(Option::as_ref)
+                        .map(DerivationTree::intermediate_stems)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|stem| MorphemeKey {
+                            kind: MorphemeKind::Stem,
+                            text: stem.to_string(),
+                        });
+                    let keys = segmentation
+                        .morphemes
+                        .iter()
+                        .map(MorphemeKey::from)
+                        .chain(stems);
+                    for key in keys {
+                        let entry = embeddings.entry(key).or_default();
+                        entry.add_word(word_index);
+                        for features in &observations {
+                            entry.add_weighted(features, segmentation.probability);
+                        }
+                    }
                 }
             }
             None => {
-                println!("- {canonical}: no Oxford entry available in the bundled data");
+                println!("- {canonical}: no dictionary entry available");
             }
         }
     }
 
     if used_dictionary_entries == 0 {
+        return None;
+    }
+
+    let morpheme_types = embeddings.len();
+    let mut sorted: Vec<(MorphemeKey, EmbeddingAccumulator)> = embeddings.into_iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(&b.0));
+    sorted.retain(|(_, accumulator)| accumulator.words() >= options.min_count);
+    let mut vectors: Vec<SparseVector> = sorted
+        .iter()
+        .map(|(_, accumulator)| accumulator.mean())
+        .collect();
+
+    println!();
+    if sorted.len() < morpheme_types {
         println!(
-            "No dictionary entries matched the provided words. Try using the bundled examples."
+            "Dropped {} of {morpheme_types} morphemes seen in fewer than {} words",
+            morpheme_types - sorted.len(),
+            options.min_count
         );
-        return Ok(());
+    }
+    if let Some(k) = options.dims
+        && !vectors.is_empty()
+    {
+        let svd = TruncatedSvd::fit(&vectors, dimension_names.len(), k, options.seed);
+        println!(
+            "Latent semantic analysis: {} of {} features kept as dimensions, {:.1}% of variance retained",
+            svd.dimensions(),
+            dimension_names.len(),
+            100.0 * svd.explained_variance()
+        );
+        for vector in &mut vectors {
+            *vector = svd.project(vector).into();
+        }
+        dimension_names = (1..=svd.dimensions())
+            .map(|component| format!("lsa.{component}"))
+            .collect();
     }
 
-    println!();
-    let dims = dimensions.unwrap_or(0);
+    let dims = dimension_names.len();
     println!(
         "Derived morpheme embedding matrix ({} morphemes × {} features):",
-        embeddings.len(),
+        sorted.len(),
         dims
     );
+    let dense = dims <= DENSE_COLUMNS;
+    if dense {
+        println!("  dimensions: {}", dimension_names.join(", "));
+    }
+    let pretty = |vector: &SparseVector| {
+        if dense {
+            let values = vector
+                .to_dense(dims)
+                .iter()
+                .map(|value| format!("{value:.3}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("[{values}]")
+        } else {
+            let values = vector
+                .entries()
+                .iter()
+                .map(|&(index, value)| format!("{}: {value:.3}", dimension_names[index]))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{{{values}}}")
+        }
+    };
 
-    let mut sorted: Vec<(MorphemeKey, Vec<f32>)> = embeddings
-        .into_iter()
-        .map(|(phoneme, acc)| (phoneme, acc.mean()))
-        .collect();
-    sorted.sort_by(|a, b| a.0.cmp(&b.0));
-
-    for (key, vector) in sorted {
-        let pretty = vector
-            .iter()
-            .map(|value| format!("{value:.3}"))
-            .collect::<Vec<_>>()
-            .join(", ");
-        println!("  {:<22} -> [{pretty}]", key.describe());
+    for ((key, accumulator), vector) in sorted.iter().zip(&vectors) {
+        println!(
+            "  {:<22} -> {} (n={})",
+            key.describe(),
+            pretty(vector),
+            accumulator.words()
+        );
+        if options.stats {
+            let stats = accumulator.statistics();
+            let rows = [
+                ("variance", stats.variance.as_ref()),
+                ("std error", stats.std_error.as_ref()),
+                ("95% ci low", stats.ci_low.as_ref()),
+                ("95% ci high", stats.ci_high.as_ref()),
+                ("min", Some(&stats.min)),
+                ("max", Some(&stats.max)),
+            ];
+            for (label, vector) in rows {
+                match vector {
+                    Some(vector) => println!("    {label:<20} -> {}", pretty(vector)),
+                    None => println!("    {label:<20} -> n/a (seen once)"),
+                }
+            }
+        }
     }
 
-    Ok(())
+    Some(RunSummary {
+        words: used_dictionary_entries,
+        morpheme_tokens,
+        morpheme_types,
+    })
 }
 
-fn build_dictionary() -> BTreeMap<&'static str, &'static str> {
-    DICTIONARY_ENTRIES.iter().cloned().collect()
+fn load_dictionary(options: &Options) -> Result<Dictionary, Box<dyn Error>> {
+    let mut dictionary = if options.replace_dictionary {
+        Dictionary::default()
+    } else {
+        Dictionary::builtin()
+    };
+
+    for source in &options.dictionaries {
+        let (path, report) = match source {
+            DictionarySource::File(path) => (path, dictionary.load_file(path)?),
+            DictionarySource::Kaikki(path) => (
+                path,
+                dictionary.import_kaikki(path, &options.kaikki_filter)?,
+            ),
+        };
+        for issue in &report.issues {
+            eprintln!(
+                "warning: {}:{}: {}",
+                path.display(),
+                issue.line,
+                issue.message
+            );
+        }
+        println!(
+            "Loaded {} dictionary entries ({} senses, {} gold segmentations) from {} ({} malformed records skipped)",
+            report.records,
+            report.senses,
+            report.segmentations,
+            path.display(),
+            report.issues.len()
+        );
+    }
+
+    let sources: Vec<String> = dictionary
+        .provenance()
+        .into_iter()
+        .map(|(attribution, senses)| match attribution {
+            Some(attribution) => format!("{senses} senses from {attribution}"),
+            None => format!("{senses} senses unattributed"),
+        })
+        .collect();
+    if !sources.is_empty() {
+        println!("Definition sources: {}", sources.join("; "));
+    }
+
+    Ok(dictionary)
 }
 
 fn read_words_from_file(path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
@@ -222,12 +663,330 @@ fn read_words_from_file(path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
     Ok(words)
 }
 
-fn segment_into_morphemes(word: &str) -> Vec<Morpheme> {
-    let cleaned: String = word
-        .chars()
+/// Which algorithm turns a word into morphemes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum SegmenterKind {
+    /// Peel the best prefixes and suffixes, then match roots left to right.
+    #[default]
+    Greedy,
+    /// Score every `prefix* root+ suffix*` split and keep the cheapest.
+    Viterbi,
+    /// Learn a morph lexicon from the word list (Morfessor Baseline MDL).
+    Morfessor,
+    /// Byte-pair-encoding subwords learned from the word list.
+    Bpe,
+    /// Unigram language model subwords learned from the word list.
+    Unigram,
+}
+
+impl SegmenterKind {
+    fn label(self) -> &'static str {
+        match self {
+            SegmenterKind::Greedy => "greedy",
+            SegmenterKind::Viterbi => "viterbi",
+            SegmenterKind::Morfessor => "morfessor",
+            SegmenterKind::Bpe => "bpe",
+            SegmenterKind::Unigram => "unigram",
+        }
+    }
+
+    /// Whether the segmenter learns a model that can be trained, saved and
+    /// loaded.
+    fn is_trained(self) -> bool {
+        matches!(
+            self,
+            SegmenterKind::Morfessor | SegmenterKind::Bpe | SegmenterKind::Unigram
+        )
+    }
+
+    /// Whether the segmenter scores alternative analyses, as `--nbest`
+    /// needs.
+    fn ranks_alternatives(self) -> bool {
+        matches!(self, SegmenterKind::Viterbi)
+    }
+}
+
+impl FromStr for SegmenterKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "greedy" => Ok(SegmenterKind::Greedy),
+            "viterbi" => Ok(SegmenterKind::Viterbi),
+            "morfessor" => Ok(SegmenterKind::Morfessor),
+            "bpe" => Ok(SegmenterKind::Bpe),
+            "unigram" => Ok(SegmenterKind::Unigram),
+            other => Err(format!(
+                "unknown segmenter '{other}' \
+                 (expected 'greedy', 'viterbi', 'morfessor', 'bpe' or 'unigram')"
+            )),
+        }
+    }
+}
+
+/// A ready-to-use segmenter; learned backends carry their trained model.
+#[derive(Debug)]
+enum Segmenter {
+    Greedy,
+    Viterbi(viterbi::ViterbiCosts),
+    Morfessor(MorfessorModel),
+    Bpe(BpeModel),
+    Unigram(UnigramModel),
+}
+
+impl Segmenter {
+    /// Up to `n` analyses of `word`, best first, with confidences summing to
+    /// one. Segmenters without a lattice always yield a single analysis.
+    fn nbest(&self, word: &str, profile: &MorphologyProfile, n: usize) -> Vec<ScoredSegmentation> {
+        let pieces = |kind: MorphemeKind, pieces: Vec<String>| {
+            let morphemes = pieces
+                .into_iter()
+                .map(|piece| Morpheme::new(kind, piece))
+                .collect();
+            ScoredSegmentation::single(morphemes)
+        };
+        match self {
+            Segmenter::Greedy => ScoredSegmentation::single(segment_into_morphemes(word, profile)),
+            Segmenter::Viterbi(costs) => {
+                ScoredSegmentation::from_costs(viterbi::nbest(word, profile, costs, n))
+            }
+            Segmenter::Morfessor(model) => pieces(MorphemeKind::Root, model.segment(word)),
+            Segmenter::Bpe(model) => pieces(MorphemeKind::Subword, model.segment(word)),
+            Segmenter::Unigram(model) => pieces(MorphemeKind::Subword, model.segment(word)),
+        }
+    }
+}
+
+/// Default seed for every stochastic training step, so runs are
+/// reproducible.
+const TRAINING_SEED: u64 = 0x00E2_EB05;
+
+/// A segmentation model learned from a word list and persisted to disk.
+trait LearnedModel: Sized {
+    const NAME: &'static str;
+
+    fn train(corpus: &[String], options: &Options) -> Self;
+    fn load(path: &Path) -> Result<Self, Box<dyn Error>>;
+    fn save(&self, path: &Path) -> Result<(), Box<dyn Error>>;
+    /// Short size description for status lines.
+    fn summary(&self) -> String;
+}
+
+impl LearnedModel for MorfessorModel {
+    const NAME: &'static str = "Morfessor";
+
+    fn train(corpus: &[String], options: &Options) -> Self {
+        MorfessorModel::train(corpus.iter().map(String::as_str), options.seed)
+    }
+
+    fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        MorfessorModel::load(path)
+    }
+
+    fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        MorfessorModel::save(self, path)
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "{} words, {} morph types, cost {:.1} nats",
+            self.training_words(),
+            self.morph_types(),
+            self.cost()
+        )
+    }
+}
+
+impl LearnedModel for BpeModel {
+    const NAME: &'static str = "BPE";
+
+    fn train(corpus: &[String], options: &Options) -> Self {
+        BpeModel::train(corpus.iter().map(String::as_str), options.vocab_size)
+    }
+
+    fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        BpeModel::load(path)
+    }
+
+    fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        BpeModel::save(self, path)
+    }
+
+    fn summary(&self) -> String {
+        format!("{} merges", self.merges())
+    }
+}
+
+impl LearnedModel for UnigramModel {
+    const NAME: &'static str = "unigram";
+
+    fn train(corpus: &[String], options: &Options) -> Self {
+        UnigramModel::train(corpus.iter().map(String::as_str), options.vocab_size)
+    }
+
+    fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        UnigramModel::load(path)
+    }
+
+    fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        UnigramModel::save(self, path)
+    }
+
+    fn summary(&self) -> String {
+        format!("{} pieces", self.pieces())
+    }
+}
+
+fn build_segmenter(
+    kind: SegmenterKind,
+    options: &Options,
+    words: &[String],
+) -> Result<Segmenter, Box<dyn Error>> {
+    let segmenter = match kind {
+        SegmenterKind::Greedy => Segmenter::Greedy,
+        SegmenterKind::Viterbi => Segmenter::Viterbi(viterbi::ViterbiCosts::default()),
+        SegmenterKind::Morfessor => Segmenter::Morfessor(learned_model(options, words)?),
+        SegmenterKind::Bpe => Segmenter::Bpe(learned_model(options, words)?),
+        SegmenterKind::Unigram => Segmenter::Unigram(learned_model(options, words)?),
+    };
+    Ok(segmenter)
+}
+
+/// Loads the model named by `--segmenter-model`, or trains one, then saves it
+/// if `--save-segmenter-model` was given.
+fn learned_model<M: LearnedModel>(
+    options: &Options,
+    words: &[String],
+) -> Result<M, Box<dyn Error>> {
+    let model = match &options.segmenter_model {
+        Some(path) => {
+            let model = M::load(path)?;
+            println!(
+                "Loaded {} model from {} ({})",
+                M::NAME,
+                path.display(),
+                model.summary()
+            );
+            model
+        }
+        None => {
+            let corpus = training_corpus(options, words)?;
+            let model = M::train(&corpus, options);
+            println!("Trained {} model ({})", M::NAME, model.summary());
+            model
+        }
+    };
+    if let Some(path) = &options.save_segmenter_model {
+        model.save(path)?;
+        println!("Saved {} model to {}", M::NAME, path.display());
+    }
+    Ok(model)
+}
+
+/// Loads the scaling statistics named by `--scaler`, or fits `--scale` on
+/// every loaded definition, then saves them if `--save-scaler` was given.
+fn build_scaler(
+    options: &Options,
+    features: &FeatureSet,
+    entries: &[&DictionaryEntry],
+) -> Result<Option<Scaler>, Box<dyn Error>> {
+    let dimensions = features.dimension_names();
+    let scaler = match (&options.scaler, options.scale) {
+        (Some(path), _) => {
+            let scaler = Scaler::load(path)?;
+            if scaler.dimensions() != dimensions {
+                return Err(format!(
+                    "scaling statistics in {} are for other features ({} dimensions, expected {})",
+                    path.display(),
+                    scaler.dimensions().len(),
+                    dimensions.len()
+                )
+                .into());
+            }
+            println!(
+                "Loaded {} scaling of {} dimensions from {}",
+                scaler.method(),
+                dimensions.len(),
+                path.display()
+            );
+            scaler
+        }
+        (None, Some(method)) => {
+            let vectors: Vec<SparseVector> = entries
+                .iter()
+                .map(|entry| features.extract(entry))
+                .collect();
+            let scaler = Scaler::fit(method, dimensions, &vectors);
+            println!(
+                "Fitted {method} scaling of {} dimensions on {} definitions",
+                scaler.dimensions().len(),
+                vectors.len()
+            );
+            scaler
+        }
+        (None, None) => return Ok(None),
+    };
+    if let Some(path) = &options.save_scaler {
+        scaler.save(path)?;
+        println!("Saved scaling statistics to {}", path.display());
+    }
+    Ok(Some(scaler))
+}
+
+/// Words used to train learned segmenters: `--train-corpus` if given,
+/// otherwise the words being processed.
+fn training_corpus(options: &Options, words: &[String]) -> Result<Vec<String>, Box<dyn Error>> {
+    match &options.train_corpus {
+        Some(path) => read_words_from_file(path),
+        None => Ok(words.to_vec()),
+    }
+}
+
+/// One analysis of a word together with its share of the probability mass.
+#[derive(Debug, Clone)]
+struct ScoredSegmentation {
+    morphemes: Vec<Morpheme>,
+    /// Total cost (negative log-score) reported by the segmenter.
+    cost: f32,
+    /// Softmax of the negated costs over the returned analyses.
+    probability: f32,
+}
+
+impl ScoredSegmentation {
+    fn single(morphemes: Vec<Morpheme>) -> Vec<Self> {
+        vec![Self {
+            morphemes,
+            cost: 0.0,
+            probability: 1.0,
+        }]
+    }
+
+    fn from_costs(analyses: Vec<(Vec<Morpheme>, f32)>) -> Vec<Self> {
+        let Some(best) = analyses.iter().map(|(_, cost)| *cost).reduce(f32::min) else {
+            return Vec::new();
+        };
+        let total: f32 = analyses.iter().map(|(_, cost)| (best - cost).exp()).sum();
+        analyses
+            .into_iter()
+            .map(|(morphemes, cost)| Self {
+                morphemes,
+                cost,
+                probability: (best - cost).exp() / total,
+            })
+            .collect()
+    }
+}
+
+/// Keeps only ASCII letters, lowercased; every segmenter works on this form.
+fn clean_word(word: &str) -> String {
+    word.chars()
         .filter(|c| c.is_ascii_alphabetic())
         .map(|c| c.to_ascii_lowercase())
-        .collect();
+        .collect()
+}
+
+fn segment_into_morphemes(word: &str, profile: &MorphologyProfile) -> Vec<Morpheme> {
+    let cleaned = clean_word(word);
     if cleaned.is_empty() {
         return Vec::new();
     }
@@ -235,33 +994,18 @@ fn segment_into_morphemes(word: &str) -> Vec<Morpheme> {
     let mut prefix_matches: Vec<&str> = Vec::new();
     let mut working = cleaned.as_str();
 
-    loop {
-        if let Some(prefix) = PREFIXES
-            .iter()
-            .find(|candidate| working.starts_with(*candidate))
-        {
-            if prefix.len() >= working.len() {
-                break;
-            }
-            prefix_matches.push(*prefix);
-            working = &working[prefix.len()..];
-        } else {
-            break;
-        }
+    // Affixes never swallow the whole remainder; something must be left
+    // over for the root.
+    while let Some(prefix) = profile.best_match(MorphemeKind::Prefix, working, working.len() - 1) {
+        prefix_matches.push(prefix.text.as_str());
+        working = &working[prefix.text.len()..];
     }
 
     let mut suffix_matches: Vec<&str> = Vec::new();
     let mut core = working;
-    loop {
-        if let Some(suffix) = SUFFIXES.iter().find(|candidate| core.ends_with(*candidate)) {
-            if suffix.len() >= core.len() {
-                break;
-            }
-            suffix_matches.push(*suffix);
-            core = &core[..core.len() - suffix.len()];
-        } else {
-            break;
-        }
+    while let Some(suffix) = profile.best_match(MorphemeKind::Suffix, core, core.len() - 1) {
+        suffix_matches.push(suffix.text.as_str());
+        core = &core[..core.len() - suffix.text.len()];
     }
 
     let mut segments: Vec<Morpheme> = prefix_matches
@@ -269,7 +1013,20 @@ fn segment_into_morphemes(word: &str) -> Vec<Morpheme> {
         .map(|prefix| Morpheme::new(MorphemeKind::Prefix, prefix))
         .collect();
 
-    let mut root_segments = decompose_root_segments(core);
+    // A core that is no root as spelled may still be one once the spelling
+    // change caused by the first suffix is undone ("happi" + "ness").
+    let restored = suffix_matches.last().and_then(|suffix| {
+        if profile.lookup(MorphemeKind::Root, core).is_some() {
+            return None;
+        }
+        orthography::restore(core, suffix, profile.alternations(), |form| {
+            profile.lookup(MorphemeKind::Root, form).is_some()
+        })
+    });
+    let mut root_segments = match restored {
+        Some(underlying) => vec![Morpheme::alternant(MorphemeKind::Root, core, underlying)],
+        None => decompose_root_segments(core, profile),
+    };
     if root_segments.is_empty() {
         if !core.is_empty() {
             root_segments.push(Morpheme::new(MorphemeKind::Root, core));
@@ -289,7 +1046,7 @@ fn segment_into_morphemes(word: &str) -> Vec<Morpheme> {
     segments
 }
 
-fn decompose_root_segments(core: &str) -> Vec<Morpheme> {
+fn decompose_root_segments(core: &str, profile: &MorphologyProfile) -> Vec<Morpheme> {
     if core.is_empty() {
         return Vec::new();
     }
@@ -298,11 +1055,10 @@ fn decompose_root_segments(core: &str) -> Vec<Morpheme> {
     let mut roots = Vec::new();
 
     while !remainder.is_empty() {
-        if let Some(pattern) = ROOT_PATTERNS
-            .iter()
-            .find(|candidate| remainder.starts_with(*candidate))
+        if let Some(slice) = profile
+            .best_match(MorphemeKind::Root, remainder, remainder.len())
+            .map(|spec| spec.text.as_str())
         {
-            let slice = *pattern;
             let len = slice.len();
             if len == 0 {
                 break;
@@ -326,71 +1082,68 @@ fn decompose_root_segments(core: &str) -> Vec<Morpheme> {
     roots
 }
 
-fn definition_to_features(definition: &str) -> Vec<f32> {
-    let tokens: Vec<String> = definition
-        .split(|c: char| !c.is_ascii_alphabetic())
-        .filter(|token| !token.is_empty())
-        .map(|token| token.to_ascii_lowercase())
-        .collect();
-
-    if tokens.is_empty() {
-        return vec![0.0; 5];
-    }
-
-    let word_count = tokens.len() as f32;
-    let total_chars: usize = tokens.iter().map(|token| token.len()).sum();
-    let avg_word_length = total_chars as f32 / word_count;
-
-    let sensory_hits = tokens
-        .iter()
-        .filter(|token| SENSORY_KEYWORDS.contains(&token.as_str()))
-        .count() as f32;
-
-    let abstract_hits = tokens
-        .iter()
-        .filter(|token| ABSTRACT_KEYWORDS.contains(&token.as_str()))
-        .count() as f32;
-
-    let unique_tokens = tokens
-        .iter()
-        .collect::<std::collections::HashSet<_>>()
-        .len() as f32;
-
-    let multi_syllable_estimate = tokens.iter().filter(|token| token.len() >= 7).count() as f32;
-
-    vec![
-        word_count,
-        avg_word_length,
-        sensory_hits / word_count,
-        abstract_hits / word_count,
-        unique_tokens / word_count.max(1.0) + multi_syllable_estimate / word_count,
-    ]
-}
-
 #[derive(Debug, Clone)]
 struct Morpheme {
     kind: MorphemeKind,
-    text: String,
+    /// The segment exactly as it is spelled inside the word.
+    surface: String,
+    /// The canonical morpheme it realises; differs from `surface` only when
+    /// a spelling alternation applied (e.g. "happi" for "happy").
+    underlying: String,
 }
 
 impl Morpheme {
     fn new(kind: MorphemeKind, text: impl Into<String>) -> Self {
+        let surface = text.into();
+        Self {
+            kind,
+            underlying: surface.clone(),
+            surface,
+        }
+    }
+
+    fn alternant(kind: MorphemeKind, surface: &str, underlying: String) -> Self {
         Self {
             kind,
-            text: text.into(),
+            surface: surface.to_string(),
+            underlying,
         }
     }
 
     fn display(&self) -> String {
-        format!("{}({})", self.kind.label(), self.text)
+        if self.surface == self.underlying {
+            format!("{}({})", self.kind.label(), self.surface)
+        } else {
+            format!(
+                "{}({}→{})",
+                self.kind.label(),
+                self.surface,
+                self.underlying
+            )
+        }
     }
 }
 
+fn breakdown(morphemes: &[Morpheme]) -> String {
+    morphemes
+        .iter()
+        .map(Morpheme::display)
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 enum MorphemeKind {
     Prefix,
     Root,
     Suffix,
+    /// A statistical piece (BPE, unigram LM) with no morphological role.
+    Subword,
+    /// A linking element between the parts of a compound ("sport-s-man").
+    Linker,
+    /// An intermediate derived stem ("establishment" inside
+    /// "disestablishment"), only produced with `--derivation`.
+    Stem,
 }
 
 impl MorphemeKind {
@@ -399,6 +1152,9 @@ impl MorphemeKind {
             MorphemeKind::Prefix => "prefix",
             MorphemeKind::Root => "root",
             MorphemeKind::Suffix => "suffix",
+            MorphemeKind::Subword => "subword",
+            MorphemeKind::Linker => "linker",
+            MorphemeKind::Stem => "stem",
         }
     }
 }
@@ -419,50 +1175,315 @@ impl From<&Morpheme> for MorphemeKey {
     fn from(morpheme: &Morpheme) -> Self {
         Self {
             kind: morpheme.kind,
-            text: morpheme.text.clone(),
+            text: morpheme.underlying.clone(),
         }
     }
 }
 
-#[derive(Debug)]
+/// How the senses of a polysemous headword feed the morpheme embeddings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum SenseMode {
+    /// Average the per-sense feature vectors into one observation per word.
+    #[default]
+    Average,
+    /// Record every sense as its own observation.
+    Separate,
+}
+
+impl SenseMode {
+    fn observations(self, sense_features: Vec<SparseVector>) -> Vec<SparseVector> {
+        match self {
+            SenseMode::Separate => sense_features,
+            SenseMode::Average => {
+                if sense_features.is_empty() {
+                    return Vec::new();
+                }
+                let mut senses = EmbeddingAccumulator::new();
+                for features in &sense_features {
+                    senses.add(features);
+                }
+                vec![senses.mean()]
+            }
+        }
+    }
+}
+
+impl FromStr for SenseMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "average" => Ok(SenseMode::Average),
+            "separate" => Ok(SenseMode::Separate),
+            other => Err(format!(
+                "unknown sense mode '{other}' (expected 'average' or 'separate')"
+            )),
+        }
+    }
+}
+
+/// Running per-dimension statistics of weighted sparse feature vectors,
+/// updated with Welford's method. Only dimensions that some observation
+/// actually set are stored; the zeros an observation implies for the others
+/// are folded in lazily, in one batch, when the dimension is next touched or
+/// the statistics are read.
+#[derive(Debug, Default)]
 struct EmbeddingAccumulator {
-    sum: Vec<f32>,
+    dimensions: BTreeMap<usize, RunningStats>,
+    /// Observations added.
     count: usize,
+    /// Distinct source words, whatever their number of analyses and senses.
+    words: usize,
+    last_word: Option<usize>,
+    weight: f32,
+    /// Sum of squared weights, for the effective sample size.
+    weight_squares: f32,
 }
 
 impl EmbeddingAccumulator {
-    fn new(dimensions: usize) -> Self {
-        Self {
-            sum: vec![0.0; dimensions],
-            count: 0,
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn add(&mut self, vector: &SparseVector) {
+        self.add_weighted(vector, 1.0);
+    }
+
+    fn add_weighted(&mut self, vector: &SparseVector, weight: f32) {
+        for &(index, value) in vector.entries() {
+            let stats = self.dimensions.entry(index).or_default();
+            stats.pad(self.count, self.weight);
+            stats.push(value, weight);
+        }
+        self.count += 1;
+        self.weight += weight;
+        self.weight_squares += weight * weight;
+    }
+
+    /// Counts word number `word` as a source of this morpheme, once
+    /// however many of its observations are added. Words arrive in order.
+    fn add_word(&mut self, word: usize) {
+        if self.last_word != Some(word) {
+            self.last_word = Some(word);
+            self.words += 1;
+        }
+    }
+
+    fn words(&self) -> usize {
+        self.words
+    }
+
+    /// Every stored dimension with the zeros of later observations folded
+    /// in.
+    fn padded(&self) -> impl Iterator<Item = (usize, RunningStats)> + '_ {
+        self.dimensions.iter().map(|(&index, stats)| {
+            let mut stats = *stats;
+            stats.pad(self.count, self.weight);
+            (index, stats)
+        })
+    }
+
+    fn mean(&self) -> SparseVector {
+        if self.weight <= 0.0 {
+            return SparseVector::default();
         }
+        SparseVector::from_entries(
+            self.padded()
+                .map(|(index, stats)| (index, stats.mean))
+                .collect(),
+        )
     }
 
-    fn add(&mut self, vector: &[f32]) {
-        if self.sum.len() != vector.len() {
-            self.sum.resize(vector.len(), 0.0);
+    fn statistics(&self) -> MorphemeStatistics {
+        let collect = |value: &dyn Fn(&RunningStats) -> f32| {
+            SparseVector::from_entries(
+                self.padded()
+                    .map(|(index, stats)| (index, value(&stats)))
+                    .collect(),
+            )
+        };
+        // Unbiased for reliability weights; with unit weights this is the
+        // usual `n - 1` denominator. One observation has no variance.
+        let denominator = self.weight - self.weight_squares / self.weight.max(f32::MIN_POSITIVE);
+        let variance = (self.count >= 2 && denominator > 0.0)
+            .then(|| collect(&|stats| stats.m2 / denominator));
+        let effective_size = self.weight * self.weight / self.weight_squares.max(f32::MIN_POSITIVE);
+        let std_error = variance.as_ref().map(|variance| {
+            SparseVector::from_entries(
+                variance
+                    .entries()
+                    .iter()
+                    .map(|&(index, value)| (index, (value / effective_size).sqrt()))
+                    .collect(),
+            )
+        });
+        // Normal approximation: the mean plus or minus z standard errors.
+        let interval = |z: f32| {
+            variance.is_some().then(|| {
+                collect(&|stats| stats.mean + z * (stats.m2 / denominator / effective_size).sqrt())
+            })
+        };
+        MorphemeStatistics {
+            ci_low: interval(-CONFIDENCE_Z),
+            ci_high: interval(CONFIDENCE_Z),
+            variance,
+            std_error,
+            min: collect(&|stats| stats.min),
+            max: collect(&|stats| stats.max),
         }
+    }
+}
+
+/// Welford state of one dimension.
+#[derive(Debug, Clone, Copy)]
+struct RunningStats {
+    /// Observations folded in, zeros included.
+    count: usize,
+    weight: f32,
+    mean: f32,
+    /// Weighted sum of squared deviations from the mean.
+    m2: f32,
+    min: f32,
+    max: f32,
+}
 
-        for (slot, value) in self.sum.iter_mut().zip(vector.iter()) {
-            *slot += value;
+impl Default for RunningStats {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            weight: 0.0,
+            mean: 0.0,
+            m2: 0.0,
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
         }
+    }
+}
+
+impl RunningStats {
+    fn push(&mut self, value: f32, weight: f32) {
         self.count += 1;
+        self.weight += weight;
+        if self.weight > 0.0 {
+            let delta = value - self.mean;
+            self.mean += delta * weight / self.weight;
+            self.m2 += weight * delta * (value - self.mean);
+        }
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
     }
 
-    fn mean(&self) -> Vec<f32> {
-        if self.count == 0 {
-            return vec![0.0; self.sum.len()];
+    /// Folds in zeros for the observations up to `count` (of total
+    /// `weight`) that did not set this dimension, merging them as one batch
+    /// of mean 0 and no spread.
+    fn pad(&mut self, count: usize, weight: f32) {
+        if self.count >= count {
+            return;
         }
-        let scale = self.count as f32;
-        self.sum.iter().map(|value| value / scale).collect()
+        let missing = (weight - self.weight).max(0.0);
+        let combined = self.weight + missing;
+        if combined > 0.0 {
+            self.m2 += self.mean * self.mean * self.weight * missing / combined;
+            self.mean -= self.mean * missing / combined;
+        }
+        self.count = count;
+        self.weight = combined;
+        self.min = self.min.min(0.0);
+        self.max = self.max.max(0.0);
     }
 }
 
-fn expand_tilde(input: &str) -> String {
-    if let Some(stripped) = input.strip_prefix("~/") {
-        if let Ok(home) = std::env::var("HOME") {
-            return format!("{}/{}", home.trim_end_matches('/'), stripped);
+/// How much a morpheme's mean embedding can be trusted.
+#[derive(Debug)]
+struct MorphemeStatistics {
+    /// Sample variance per dimension; `None` below two observations.
+    variance: Option<SparseVector>,
+    /// Standard error of the mean per dimension; `None` with the variance.
+    std_error: Option<SparseVector>,
+    /// Bounds of the 95% confidence interval of the mean; `None` with the
+    /// variance.
+    ci_low: Option<SparseVector>,
+    ci_high: Option<SparseVector>,
+    min: SparseVector,
+    max: SparseVector,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accumulate(observations: &[(&[(usize, f32)], f32)]) -> EmbeddingAccumulator {
+        let mut accumulator = EmbeddingAccumulator::new();
+        for (entries, weight) in observations {
+            accumulator.add_weighted(&SparseVector::from_entries(entries.to_vec()), *weight);
+        }
+        accumulator
+    }
+
+    fn assert_close(vector: &SparseVector, expected: &[f32]) {
+        let values = vector.to_dense(expected.len());
+        for (value, want) in values.iter().zip(expected) {
+            assert!((value - want).abs() < 1e-5, "{values:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn running_statistics_match_the_two_pass_formulas() {
+        let accumulator = accumulate(&[(&[(0, 1.0)], 1.0), (&[(0, 2.0)], 1.0), (&[(0, 4.0)], 1.0)]);
+        // Mean 7/3 and sample variance 42/18 over three observations.
+        let variance: f32 = 42.0 / 18.0;
+        let std_error = (variance / 3.0).sqrt();
+        let stats = accumulator.statistics();
+        assert_close(&accumulator.mean(), &[7.0 / 3.0]);
+        assert_close(stats.variance.as_ref().unwrap(), &[variance]);
+        assert_close(stats.std_error.as_ref().unwrap(), &[std_error]);
+        assert_close(
+            stats.ci_low.as_ref().unwrap(),
+            &[7.0 / 3.0 - CONFIDENCE_Z * std_error],
+        );
+        assert_close(
+            stats.ci_high.as_ref().unwrap(),
+            &[7.0 / 3.0 + CONFIDENCE_Z * std_error],
+        );
+        assert_close(&stats.min, &[1.0]);
+        assert_close(&stats.max, &[4.0]);
+    }
+
+    #[test]
+    fn implicit_zeros_count_as_observations() {
+        let accumulator = accumulate(&[(&[(0, 2.0)], 1.0), (&[], 1.0), (&[(1, 3.0)], 1.0)]);
+        let stats = accumulator.statistics();
+        assert_close(&accumulator.mean(), &[2.0 / 3.0, 1.0]);
+        assert_close(stats.variance.as_ref().unwrap(), &[4.0 / 3.0, 3.0]);
+        assert_close(&stats.min, &[0.0, 0.0]);
+        assert_close(&stats.max, &[2.0, 3.0]);
+    }
+
+    #[test]
+    fn weights_shift_the_mean_and_shrink_the_sample() {
+        let accumulator = accumulate(&[(&[(0, 1.0)], 0.75), (&[(0, 5.0)], 0.25)]);
+        assert_close(&accumulator.mean(), &[2.0]);
+        // Reliability weights: Σw(x - mean)² / (Σw - Σw²/Σw) = 3 / 0.375.
+        let stats = accumulator.statistics();
+        assert_close(stats.variance.as_ref().unwrap(), &[8.0]);
+        // The effective sample size is (Σw)² / Σw² = 1.6.
+        assert_close(stats.std_error.as_ref().unwrap(), &[(8.0f32 / 1.6).sqrt()]);
+
+        let once = accumulate(&[(&[(0, 1.0)], 1.0)]);
+        let stats = once.statistics();
+        assert!(stats.variance.is_none() && stats.ci_low.is_none() && stats.ci_high.is_none());
+    }
+
+    #[test]
+    fn words_count_once_however_many_observations_they_add() {
+        let mut accumulator = EmbeddingAccumulator::new();
+        for (word, observations) in [(0, 3), (1, 1), (4, 2)] {
+            for _ in 0..observations {
+                accumulator.add_word(word);
+                accumulator.add(&SparseVector::default());
+            }
         }
+        assert_eq!(accumulator.words(), 3);
+        assert_eq!(accumulator.count, 6);
     }
-    input.to_string()
 }
@@ -0,0 +1,335 @@
+//! Unsupervised segmentation in the spirit of Morfessor Baseline: a morph
+//! lexicon is learned from a word list by minimising a two-part Minimum
+//! Description Length cost (lexicon + corpus), with no hand-written tables.
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::clean_word;
+use crate::rng::SplitMix64;
+
+/// Cost in nats of spelling one letter (26 letters plus an end marker).
+const LETTER_COST: f64 = 3.295_836_866_004_329; // ln(27)
+const MAX_EPOCHS: usize = 25;
+/// Training stops once an epoch improves the total cost by less than this
+/// fraction.
+const CONVERGENCE: f64 = 1e-4;
+const MODEL_HEADER: &str = "# erebus morfessor-baseline v1";
+
+/// A learned morph lexicon plus the analyses of its training words.
+#[derive(Debug, Clone, Default)]
+pub struct MorfessorModel {
+    analyses: BTreeMap<String, Analysis>,
+    counts: HashMap<String, u64>,
+    tokens: u64,
+    /// Running Σ c·ln(c) over morph token counts.
+    sum_count_ln_count: f64,
+    /// Running cost of spelling out every morph type.
+    lexicon_cost: f64,
+}
+
+/// How a training word splits, and how many times the word counts towards
+/// the morph counts.
+#[derive(Debug, Clone)]
+struct Analysis {
+    count: u64,
+    morphs: Vec<String>,
+}
+
+impl MorfessorModel {
+    /// Learns a lexicon from `words` by repeatedly re-splitting each word
+    /// (in seeded random order) until the MDL cost stops improving.
+    pub fn train<'a>(words: impl IntoIterator<Item = &'a str>, seed: u64) -> Self {
+        let mut model = Self::default();
+        let mut corpus: Vec<String> = words
+            .into_iter()
+            .map(clean_word)
+            .filter(|word| !word.is_empty())
+            .collect();
+        corpus.sort();
+        corpus.dedup();
+
+        for word in &corpus {
+            model.adjust(word, 1);
+            model.analyses.insert(
+                word.clone(),
+                Analysis {
+                    count: 1,
+                    morphs: vec![word.clone()],
+                },
+            );
+        }
+
+        let mut rng = SplitMix64::new(seed);
+        let mut previous = model.cost();
+        for _ in 0..MAX_EPOCHS {
+            rng.shuffle(&mut corpus);
+            for word in &corpus {
+                for morph in model.analyses[word].morphs.clone() {
+                    model.adjust(&morph, -1);
+                }
+                let morphs = model.resplit(word);
+                model
+                    .analyses
+                    .insert(word.clone(), Analysis { count: 1, morphs });
+            }
+
+            let cost = model.cost();
+            if previous - cost < CONVERGENCE * previous.abs() {
+                break;
+            }
+            previous = cost;
+        }
+
+        model
+    }
+
+    /// Loads a model saved with [`MorfessorModel::save`]: one
+    /// `count morph + morph ...` analysis per line, as in Morfessor's own
+    /// segmentation files. Morphs are cleaned like the words they are looked
+    /// up by, and each word may be analysed only once.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read Morfessor model {}: {err}", path.display()))?;
+        Self::parse(&contents, path)
+    }
+
+    fn parse(contents: &str, path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut model = Self::default();
+
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let malformed = || format!("{}:{}: malformed analysis", path.display(), index + 1);
+            let (count, analysis) = line.split_once(' ').ok_or_else(malformed)?;
+            let count: u64 = count
+                .parse()
+                .ok()
+                .filter(|count| *count >= 1)
+                .ok_or_else(malformed)?;
+            let delta = i64::try_from(count).map_err(|_| malformed())?;
+            let morphs: Vec<String> = analysis.split(" + ").map(clean_word).collect();
+            if morphs.iter().any(String::is_empty) {
+                return Err(malformed().into());
+            }
+            let word = morphs.concat();
+            if model.analyses.contains_key(&word) {
+                return Err(format!(
+                    "{}:{}: duplicate analysis of '{word}'",
+                    path.display(),
+                    index + 1
+                )
+                .into());
+            }
+            for morph in &morphs {
+                model.try_adjust(morph, delta).ok_or_else(|| {
+                    format!("{}:{}: morph count overflow", path.display(), index + 1)
+                })?;
+            }
+            model.analyses.insert(word, Analysis { count, morphs });
+        }
+
+        Ok(model)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut out = format!("{MODEL_HEADER}\n");
+        for analysis in self.analyses.values() {
+            out.push_str(&format!(
+                "{} {}\n",
+                analysis.count,
+                analysis.morphs.join(" + ")
+            ));
+        }
+        fs::write(path, out)
+            .map_err(|err| format!("cannot write Morfessor model {}: {err}", path.display()))?;
+        Ok(())
+    }
+
+    pub fn morph_types(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn training_words(&self) -> usize {
+        self.analyses.len()
+    }
+
+    /// Total description length in nats.
+    pub fn cost(&self) -> f64 {
+        let corpus_cost = if self.tokens == 0 {
+            0.0
+        } else {
+            count_ln_count(self.tokens) - self.sum_count_ln_count
+        };
+        corpus_cost + self.lexicon_cost
+    }
+
+    /// Training words keep their learned analysis; anything else is split by
+    /// Viterbi search over the lexicon, where unseen morphs pay the cost of
+    /// being added to it.
+    pub fn segment(&self, word: &str) -> Vec<String> {
+        let cleaned = clean_word(word);
+        if let Some(analysis) = self.analyses.get(&cleaned) {
+            return analysis.morphs.clone();
+        }
+        if cleaned.is_empty() {
+            return Vec::new();
+        }
+
+        let log_tokens = (self.tokens.max(1) as f64).ln();
+        let n = cleaned.len();
+        let mut best: Vec<(f64, usize)> = vec![(f64::INFINITY, 0); n + 1];
+        best[0] = (0.0, 0);
+        for end in 1..=n {
+            for start in 0..end {
+                let piece = &cleaned[start..end];
+                let cost = match self.counts.get(piece) {
+                    Some(count) => log_tokens - (*count as f64).ln(),
+                    None => log_tokens + type_cost(piece),
+                };
+                let total = best[start].0 + cost;
+                if total < best[end].0 {
+                    best[end] = (total, start);
+                }
+            }
+        }
+
+        let mut morphs = Vec::new();
+        let mut end = n;
+        while end > 0 {
+            let start = best[end].1;
+            morphs.push(cleaned[start..end].to_string());
+            end = start;
+        }
+        morphs.reverse();
+        morphs
+    }
+
+    /// Chooses between keeping `segment` whole and its cheapest binary split,
+    /// recursing into both halves when splitting wins. The chosen morphs are
+    /// left added to the counts.
+    fn resplit(&mut self, segment: &str) -> Vec<String> {
+        self.adjust(segment, 1);
+        let mut best_cost = self.cost();
+        self.adjust(segment, -1);
+
+        let mut best_split = None;
+        for index in 1..segment.len() {
+            let (left, right) = segment.split_at(index);
+            self.adjust(left, 1);
+            self.adjust(right, 1);
+            let cost = self.cost();
+            self.adjust(left, -1);
+            self.adjust(right, -1);
+            if cost < best_cost {
+                best_cost = cost;
+                best_split = Some(index);
+            }
+        }
+
+        match best_split {
+            None => {
+                self.adjust(segment, 1);
+                vec![segment.to_string()]
+            }
+            Some(index) => {
+                let (left, right) = segment.split_at(index);
+                let mut morphs = self.resplit(left);
+                morphs.extend(self.resplit(right));
+                morphs
+            }
+        }
+    }
+
+    /// Changes the token count of `morph` by `delta`, keeping the running
+    /// cost terms in sync. Training only takes back what it added.
+    fn adjust(&mut self, morph: &str, delta: i64) {
+        self.try_adjust(morph, delta)
+            .expect("training keeps morph counts consistent");
+    }
+
+    /// [`MorfessorModel::adjust`], or `None` with the model untouched when a
+    /// count would leave the range of `u64`.
+    fn try_adjust(&mut self, morph: &str, delta: i64) -> Option<()> {
+        let old = self.counts.get(morph).copied().unwrap_or(0);
+        let new = old.checked_add_signed(delta)?;
+        let tokens = self.tokens.checked_add_signed(delta)?;
+
+        self.sum_count_ln_count += count_ln_count(new) - count_ln_count(old);
+        self.tokens = tokens;
+        match (old, new) {
+            (0, 0) => {}
+            (0, _) => {
+                self.lexicon_cost += type_cost(morph);
+                self.counts.insert(morph.to_string(), new);
+            }
+            (_, 0) => {
+                self.lexicon_cost -= type_cost(morph);
+                self.counts.remove(morph);
+            }
+            _ => {
+                self.counts.insert(morph.to_string(), new);
+            }
+        }
+        Some(())
+    }
+}
+
+fn count_ln_count(count: u64) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        count as f64 * (count as f64).ln()
+    }
+}
+
+fn type_cost(morph: &str) -> f64 {
+    (morph.len() + 1) as f64 * LETTER_COST
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> Result<MorfessorModel, String> {
+        MorfessorModel::parse(contents, Path::new("model.txt")).map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn loaded_morphs_are_cleaned_like_lookups() {
+        let model = parse("2 Walk + ING\n1 re- + Play\n").expect("model is valid");
+        assert_eq!(model.segment("walking"), ["walk", "ing"]);
+        assert_eq!(model.segment("Replay!"), ["re", "play"]);
+        assert_eq!(model.counts["walk"], 2);
+        assert_eq!(model.tokens, 6);
+    }
+
+    #[test]
+    fn duplicate_and_overflowing_analyses_are_rejected() {
+        let err = parse("1 walk + ing\n2 walking\n").unwrap_err();
+        assert_eq!(err, "model.txt:2: duplicate analysis of 'walking'");
+        let err = parse("1 walk + ing\n1 Walk + ing\n").unwrap_err();
+        assert_eq!(err, "model.txt:2: duplicate analysis of 'walking'");
+
+        let huge = u64::MAX / 2;
+        let err = parse(&format!("{huge} play\n{huge} ful\n2 playful\n")).unwrap_err();
+        assert_eq!(err, "model.txt:3: morph count overflow");
+    }
+
+    #[test]
+    fn saving_and_loading_round_trips() {
+        let model = parse("3 play + ful\n2 walk + ing\n1 walk\n").expect("model is valid");
+        let path =
+            std::env::temp_dir().join(format!("erebus-{}-unit-model.txt", std::process::id()));
+        model.save(&path).expect("model is saved");
+        let reloaded = MorfessorModel::load(&path).expect("saved model loads");
+        assert_eq!(reloaded.counts, model.counts);
+        assert_eq!(reloaded.tokens, model.tokens);
+        assert!((reloaded.cost() - model.cost()).abs() < 1e-9);
+        fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,433 @@
+//! Prefix, suffix and root inventories consulted by the segmenter, plus the
+//! spelling alternations applied at morpheme boundaries. The built-in tables
+//! form the default profile; `--morphology` swaps in (or extends them with) a
+//! TOML profile. Each inventory is indexed by a trie so
+//! matching picks the best candidate rather than the first one listed.
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::MorphemeKind;
+use crate::orthography::Alternation;
+use crate::toml::{self, TomlTable, TomlValue};
+
+const PREFIXES: [&str; 11] = [
+    "hyper", "trans", "inter", "sesqui", "anti", "ab", "de", "dis", "ultra", "pseudo", "super",
+];
+
+const SUFFIXES: [&str; 17] = [
+    "arianism",
+    "ification",
+    "mogrification",
+    "ulation",
+    "arian",
+    "ation",
+    "mentation",
+    "iasis",
+    "iosis",
+    "osis",
+    "ulate",
+    "icism",
+    "ism",
+    "ious",
+    "ian",
+    "ness",
+    "ment",
+];
+
+const ROOT_PATTERNS: [&str; 21] = [
+    "antidisestablish",
+    "establish",
+    "transmogr",
+    "cattywampus",
+    "sesquiped",
+    "biblio",
+    "bibli",
+    "hyper",
+    "mogr",
+    "meta",
+    "morph",
+    "klept",
+    "fenestr",
+    "squat",
+    "wampus",
+    "pedal",
+    "ped",
+    "kerfuffle",
+    "fic",
+    "ruffle",
+    "fuffle",
+];
+
+const LINKERS: [&str; 2] = ["s", "o"];
+
+/// One affix or root known to a profile.
+#[derive(Debug, Clone)]
+pub struct MorphSpec {
+    pub text: String,
+    pub gloss: Option<String>,
+    pub origin: Option<String>,
+    /// Higher priorities win over longer matches; defaults to 0.
+    pub priority: i64,
+}
+
+impl MorphSpec {
+    fn bare(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            gloss: None,
+            origin: None,
+            priority: 0,
+        }
+    }
+}
+
+/// The morpheme inventories used for segmentation, kept in declaration order.
+#[derive(Debug, Clone, Default)]
+pub struct MorphologyProfile {
+    pub name: String,
+    prefixes: Vec<MorphSpec>,
+    suffixes: Vec<MorphSpec>,
+    roots: Vec<MorphSpec>,
+    alternations: Vec<Alternation>,
+    /// Linking elements allowed between the parts of a compound.
+    linkers: Vec<String>,
+    prefix_trie: AffixTrie,
+    suffix_trie: AffixTrie,
+    root_trie: AffixTrie,
+}
+
+impl MorphologyProfile {
+    pub fn builtin() -> Self {
+        let bare = |texts: &[&str]| texts.iter().map(|text| MorphSpec::bare(text)).collect();
+        let mut profile = Self {
+            name: "builtin".to_string(),
+            prefixes: bare(&PREFIXES),
+            suffixes: bare(&SUFFIXES),
+            roots: bare(&ROOT_PATTERNS),
+            alternations: Alternation::builtin(),
+            linkers: LINKERS.iter().map(|linker| linker.to_string()).collect(),
+            ..Self::default()
+        };
+        profile.reindex();
+        profile
+    }
+
+    /// Loads a TOML profile. `extends = "builtin"` starts from the built-in
+    /// tables; entries repeating an existing morpheme update it in place.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read morphology profile {}: {err}", path.display()))?;
+        let document =
+            toml::parse(&contents).map_err(|err| format!("{}: {err}", path.display()))?;
+        let default_name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Self::from_toml(&document, default_name)
+            .map_err(|message| format!("{}: {message}", path.display()).into())
+    }
+
+    fn from_toml(document: &TomlTable, default_name: String) -> Result<Self, String> {
+        let mut profile = match document.get("extends").map(|value| value.as_str()) {
+            None => Self::default(),
+            Some(Some("builtin")) => Self::builtin(),
+            Some(_) => return Err("\"extends\" only supports \"builtin\"".to_string()),
+        };
+        profile.name = match document.get("name") {
+            None => default_name,
+            Some(TomlValue::String(name)) => name.clone(),
+            Some(other) => {
+                return Err(format!(
+                    "\"name\" must be a string, found {}",
+                    other.type_name()
+                ));
+            }
+        };
+
+        for (key, value) in &document.entries {
+            let kind = match key.as_str() {
+                "name" | "extends" => continue,
+                "alternations" => {
+                    profile.add_alternations(value)?;
+                    continue;
+                }
+                "linkers" => {
+                    profile.add_linkers(value)?;
+                    continue;
+                }
+                "prefixes" => MorphemeKind::Prefix,
+                "suffixes" => MorphemeKind::Suffix,
+                "roots" => MorphemeKind::Root,
+                other => return Err(format!("unknown key '{other}'")),
+            };
+            let TomlValue::Array(items) = value else {
+                return Err(format!(
+                    "\"{key}\" must be an array, found {}",
+                    value.type_name()
+                ));
+            };
+            for (index, item) in items.iter().enumerate() {
+                let spec = spec_from_toml(item)
+                    .map_err(|message| format!("{key} entry {}: {message}", index + 1))?;
+                profile.add(kind, spec);
+            }
+        }
+
+        profile.reindex();
+        Ok(profile)
+    }
+
+    fn reindex(&mut self) {
+        self.prefix_trie = AffixTrie::build(&self.prefixes, Anchor::Start);
+        self.suffix_trie = AffixTrie::build(&self.suffixes, Anchor::End);
+        self.root_trie = AffixTrie::build(&self.roots, Anchor::Start);
+    }
+
+    pub fn inventory(&self, kind: MorphemeKind) -> &[MorphSpec] {
+        match kind {
+            MorphemeKind::Prefix => &self.prefixes,
+            MorphemeKind::Root => &self.roots,
+            MorphemeKind::Suffix => &self.suffixes,
+            MorphemeKind::Subword | MorphemeKind::Linker | MorphemeKind::Stem => &[],
+        }
+    }
+
+    pub fn lookup(&self, kind: MorphemeKind, text: &str) -> Option<&MorphSpec> {
+        self.inventory(kind).iter().find(|spec| spec.text == text)
+    }
+
+    /// Every entry of `kind` that `text` starts with (prefixes, roots) or
+    /// ends with (suffixes).
+    pub fn candidates<'a>(
+        &'a self,
+        kind: MorphemeKind,
+        text: &str,
+    ) -> impl Iterator<Item = &'a MorphSpec> + 'a {
+        let matches = match kind {
+            MorphemeKind::Prefix => self.prefix_trie.matches(text),
+            MorphemeKind::Root => self.root_trie.matches(text),
+            MorphemeKind::Suffix => self.suffix_trie.matches(text),
+            MorphemeKind::Subword | MorphemeKind::Linker | MorphemeKind::Stem => Vec::new(),
+        };
+        let inventory = self.inventory(kind);
+        matches.into_iter().map(move |index| &inventory[index])
+    }
+
+    /// The preferred entry of `kind` anchored at the matching edge of `text`
+    /// and at most `max_len` bytes long: highest priority first, then the
+    /// longest match, so table order never decides the outcome.
+    pub fn best_match(&self, kind: MorphemeKind, text: &str, max_len: usize) -> Option<&MorphSpec> {
+        self.candidates(kind, text)
+            .filter(|spec| spec.text.len() <= max_len)
+            .max_by_key(|spec| (spec.priority, spec.text.len()))
+    }
+
+    /// Spelling rules tried, in order, when a segment is not a known
+    /// morpheme by itself.
+    pub fn alternations(&self) -> &[Alternation] {
+        &self.alternations
+    }
+
+    pub fn linkers(&self) -> &[String] {
+        &self.linkers
+    }
+
+    fn add_linkers(&mut self, value: &TomlValue) -> Result<(), String> {
+        let TomlValue::Array(items) = value else {
+            return Err(format!(
+                "\"linkers\" must be an array, found {}",
+                value.type_name()
+            ));
+        };
+        for (index, item) in items.iter().enumerate() {
+            let linker = item
+                .as_str()
+                .filter(|linker| {
+                    !linker.is_empty() && linker.bytes().all(|b| b.is_ascii_lowercase())
+                })
+                .ok_or_else(|| {
+                    format!("linkers entry {}: expected lowercase letters", index + 1)
+                })?;
+            if !self.linkers.iter().any(|existing| existing == linker) {
+                self.linkers.push(linker.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends rules from an `alternations` array of tables; a rule with the
+    /// same surface and underlying ending as an existing one replaces it.
+    fn add_alternations(&mut self, value: &TomlValue) -> Result<(), String> {
+        let TomlValue::Array(items) = value else {
+            return Err(format!(
+                "\"alternations\" must be an array, found {}",
+                value.type_name()
+            ));
+        };
+        for (index, item) in items.iter().enumerate() {
+            let TomlValue::Table(table) = item else {
+                return Err(format!(
+                    "alternations entry {}: expected table, found {}",
+                    index + 1,
+                    item.type_name()
+                ));
+            };
+            let rule = Alternation::from_toml(table)
+                .map_err(|message| format!("alternations entry {}: {message}", index + 1))?;
+            match self.alternations.iter_mut().find(|existing| {
+                existing.surface == rule.surface && existing.underlying == rule.underlying
+            }) {
+                Some(existing) => *existing = rule,
+                None => self.alternations.push(rule),
+            }
+        }
+        Ok(())
+    }
+
+    fn add(&mut self, kind: MorphemeKind, spec: MorphSpec) {
+        let inventory = match kind {
+            MorphemeKind::Prefix => &mut self.prefixes,
+            MorphemeKind::Root => &mut self.roots,
+            MorphemeKind::Suffix => &mut self.suffixes,
+            MorphemeKind::Subword | MorphemeKind::Linker | MorphemeKind::Stem => {
+                unreachable!("profiles have no subword, linker or stem table")
+            }
+        };
+        match inventory
+            .iter_mut()
+            .find(|existing| existing.text == spec.text)
+        {
+            Some(existing) => *existing = spec,
+            None => inventory.push(spec),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Anchor {
+    Start,
+    End,
+}
+
+/// Byte trie over one inventory. Suffix tries store their entries reversed
+/// so a single walk from the end of a word finds every matching suffix.
+#[derive(Debug, Clone, Default)]
+struct AffixTrie {
+    nodes: Vec<TrieNode>,
+    anchor: Option<Anchor>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct TrieNode {
+    children: Vec<(u8, usize)>,
+    entry: Option<usize>,
+}
+
+impl AffixTrie {
+    fn build(specs: &[MorphSpec], anchor: Anchor) -> Self {
+        let mut trie = Self {
+            nodes: vec![TrieNode::default()],
+            anchor: Some(anchor),
+        };
+        for (index, spec) in specs.iter().enumerate() {
+            let mut node = 0;
+            for byte in Self::walk(spec.text.as_bytes(), anchor) {
+                node = match trie.nodes[node].children.iter().find(|(b, _)| *b == byte) {
+                    Some(&(_, child)) => child,
+                    None => {
+                        trie.nodes.push(TrieNode::default());
+                        let child = trie.nodes.len() - 1;
+                        trie.nodes[node].children.push((byte, child));
+                        child
+                    }
+                };
+            }
+            trie.nodes[node].entry.get_or_insert(index);
+        }
+        trie
+    }
+
+    fn walk(bytes: &[u8], anchor: Anchor) -> Box<dyn Iterator<Item = u8> + '_> {
+        match anchor {
+            Anchor::Start => Box::new(bytes.iter().copied()),
+            Anchor::End => Box::new(bytes.iter().rev().copied()),
+        }
+    }
+
+    /// Inventory indexes of every entry anchored at the trie's edge of `text`,
+    /// shortest first.
+    fn matches(&self, text: &str) -> Vec<usize> {
+        let Some(anchor) = self.anchor else {
+            return Vec::new();
+        };
+        let mut found = Vec::new();
+        let mut node = 0;
+        for byte in Self::walk(text.as_bytes(), anchor) {
+            match self.nodes[node].children.iter().find(|(b, _)| *b == byte) {
+                Some(&(_, child)) => node = child,
+                None => break,
+            }
+            found.extend(self.nodes[node].entry);
+        }
+        found
+    }
+}
+
+/// Accepts either a bare string or a table with `text`, `gloss`, `origin`
+/// and `priority`.
+fn spec_from_toml(item: &TomlValue) -> Result<MorphSpec, String> {
+    let (text, gloss, origin, priority) = match item {
+        TomlValue::String(text) => (text.as_str(), None, None, 0),
+        TomlValue::Table(table) => {
+            for (key, _) in &table.entries {
+                if !matches!(key.as_str(), "text" | "gloss" | "origin" | "priority") {
+                    return Err(format!("unknown key '{key}'"));
+                }
+            }
+            let text = table
+                .get("text")
+                .and_then(TomlValue::as_str)
+                .ok_or("missing \"text\" string")?;
+            let optional = |key: &str| match table.get(key) {
+                None => Ok(None),
+                Some(TomlValue::String(value)) => Ok(Some(value.clone())),
+                Some(other) => Err(format!(
+                    "\"{key}\" must be a string, found {}",
+                    other.type_name()
+                )),
+            };
+            let priority = match table.get("priority") {
+                None => 0,
+                Some(TomlValue::Integer(priority)) => *priority,
+                Some(other) => {
+                    return Err(format!(
+                        "\"priority\" must be an integer, found {}",
+                        other.type_name()
+                    ));
+                }
+            };
+            (text, optional("gloss")?, optional("origin")?, priority)
+        }
+        other => {
+            return Err(format!(
+                "expected string or table, found {}",
+                other.type_name()
+            ));
+        }
+    };
+
+    let text = text.trim().to_ascii_lowercase();
+    if text.is_empty() || !text.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(format!(
+            "'{text}' must be non-empty and contain only ASCII letters"
+        ));
+    }
+    Ok(MorphSpec {
+        text,
+        gloss,
+        origin,
+        priority,
+    })
+}
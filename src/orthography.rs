@@ -0,0 +1,211 @@
+//! Spelling alternations at morpheme boundaries. Affixation changes how a
+//! stem is written ("happy" + "ness" → "happiness", "stop" + "ing" →
+//! "stopping"), so the literal slice of a word is often an allomorph. The
+//! rules here map such surface segments back to the canonical morpheme so
+//! every spelling of it shares one embedding.
+
+use crate::toml::{TomlTable, TomlValue};
+use crate::{Morpheme, MorphemeKind};
+
+const VOWELS: &str = "aeiouy";
+/// Final consonants that double before a vowel-initial suffix.
+const DOUBLING_CONSONANTS: &str = "bdgklmnprtz";
+
+/// Which letters may start the morpheme after the stem for a rule to apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    Any,
+    Vowel,
+    Consonant,
+}
+
+impl Context {
+    fn admits(self, letter: char) -> bool {
+        match self {
+            Context::Any => true,
+            Context::Vowel => VOWELS.contains(letter),
+            Context::Consonant => !VOWELS.contains(letter),
+        }
+    }
+}
+
+/// A stem ending written `surface` at a boundary stands for `underlying`,
+/// e.g. surface "i" / underlying "y" before anything but "i".
+#[derive(Debug, Clone)]
+pub struct Alternation {
+    pub name: String,
+    pub surface: String,
+    pub underlying: String,
+    pub before: Context,
+    /// Letters that block the rule even when `before` admits them.
+    pub except: String,
+}
+
+impl Alternation {
+    /// The inflectional rules plus the derivational ones: "-fy" verbs
+    /// written "-fic" before "-ation" (transmogrify → transmogrification)
+    /// and "-ent"/"-ant" adjectives losing their ending before
+    /// "-ence"/"-ance" (resilient → resilience).
+    pub fn builtin() -> Vec<Self> {
+        let mut rules = Self::inflectional();
+        rules.extend([
+            Self {
+                name: "fy-to-fic".to_string(),
+                surface: "fic".to_string(),
+                underlying: "fy".to_string(),
+                before: Context::Vowel,
+                except: String::new(),
+            },
+            Self {
+                name: "ent-to-ence".to_string(),
+                surface: String::new(),
+                underlying: "ent".to_string(),
+                before: Context::Vowel,
+                except: "aiouy".to_string(),
+            },
+            Self {
+                name: "ant-to-ance".to_string(),
+                surface: String::new(),
+                underlying: "ant".to_string(),
+                before: Context::Vowel,
+                except: "eiouy".to_string(),
+            },
+        ]);
+        rules
+    }
+
+    /// E-deletion, y→i and consonant doubling, the changes inflections
+    /// cause.
+    pub fn inflectional() -> Vec<Self> {
+        let mut rules = vec![
+            Self {
+                name: "e-deletion".to_string(),
+                surface: String::new(),
+                underlying: "e".to_string(),
+                before: Context::Vowel,
+                except: String::new(),
+            },
+            Self {
+                name: "y-to-i".to_string(),
+                surface: "i".to_string(),
+                underlying: "y".to_string(),
+                before: Context::Any,
+                except: "i".to_string(),
+            },
+        ];
+        rules.extend(DOUBLING_CONSONANTS.chars().map(|consonant| Self {
+            name: "consonant-doubling".to_string(),
+            surface: format!("{consonant}{consonant}"),
+            underlying: consonant.to_string(),
+            before: Context::Vowel,
+            except: String::new(),
+        }));
+        rules
+    }
+
+    /// The underlying form of `stem` when it is followed by `next`, if this
+    /// rule applies there.
+    pub fn restore(&self, stem: &str, next: &str) -> Option<String> {
+        let letter = next.chars().next()?;
+        if !self.before.admits(letter) || self.except.contains(letter) {
+            return None;
+        }
+        let base = stem.strip_suffix(self.surface.as_str())?;
+        if base.is_empty() {
+            return None;
+        }
+        Some(format!("{base}{}", self.underlying))
+    }
+
+    pub fn from_toml(table: &TomlTable) -> Result<Self, String> {
+        for (key, _) in &table.entries {
+            if !matches!(
+                key.as_str(),
+                "name" | "surface" | "underlying" | "before" | "except"
+            ) {
+                return Err(format!("unknown key '{key}'"));
+            }
+        }
+        let string = |key: &str| match table.get(key) {
+            None => Ok(None),
+            Some(TomlValue::String(value)) => Ok(Some(value.clone())),
+            Some(other) => Err(format!(
+                "\"{key}\" must be a string, found {}",
+                other.type_name()
+            )),
+        };
+        let surface = string("surface")?.ok_or("missing \"surface\" string")?;
+        let underlying = string("underlying")?.ok_or("missing \"underlying\" string")?;
+        if surface == underlying {
+            return Err("\"surface\" and \"underlying\" must differ".to_string());
+        }
+        let before = match string("before")?.as_deref() {
+            None | Some("any") => Context::Any,
+            Some("vowel") => Context::Vowel,
+            Some("consonant") => Context::Consonant,
+            Some(other) => {
+                return Err(format!(
+                    "unknown context '{other}' (expected 'any', 'vowel' or 'consonant')"
+                ));
+            }
+        };
+        Ok(Self {
+            name: string("name")?.unwrap_or_else(|| format!("{surface}->{underlying}")),
+            surface,
+            underlying,
+            before,
+            except: string("except")?.unwrap_or_default(),
+        })
+    }
+}
+
+/// Rewrites the underlying form of every root whose spelling is not a known
+/// morpheme but which some rule maps to one, given the morpheme after it.
+/// Forms that are already known are left alone, so "sing" + "er" never
+/// becomes "singe".
+pub fn canonicalize(
+    morphemes: &mut [Morpheme],
+    alternations: &[Alternation],
+    is_known: impl Fn(MorphemeKind, &str) -> bool,
+) {
+    for index in 0..morphemes.len().saturating_sub(1) {
+        let (stem, next) = (&morphemes[index], &morphemes[index + 1]);
+        if stem.kind != MorphemeKind::Root
+            || stem.surface != stem.underlying
+            || is_known(stem.kind, &stem.surface)
+        {
+            continue;
+        }
+        if let Some(underlying) = restore(&stem.surface, &next.surface, alternations, |form| {
+            is_known(MorphemeKind::Root, form)
+        }) {
+            morphemes[index].underlying = underlying;
+        }
+    }
+}
+
+/// The first rule-restored form of `stem` before `next` that `is_known`
+/// accepts.
+pub fn restore(
+    stem: &str,
+    next: &str,
+    alternations: &[Alternation],
+    is_known: impl Fn(&str) -> bool,
+) -> Option<String> {
+    alternations
+        .iter()
+        .filter_map(|rule| rule.restore(stem, next))
+        .find(|form| is_known(form))
+}
+
+/// The rule that turns `stem`'s surface into its underlying form before
+/// `next`, for reporting.
+pub fn explain<'a>(
+    stem: &Morpheme,
+    next: &Morpheme,
+    alternations: &'a [Alternation],
+) -> Option<&'a Alternation> {
+    alternations.iter().find(|rule| {
+        rule.restore(&stem.surface, &next.surface).as_deref() == Some(&stem.underlying)
+    })
+}
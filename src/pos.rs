@@ -0,0 +1,399 @@
+//! A small rule- and lexicon-based part-of-speech tagger for definition
+//! text, and detection of the headword's part of speech from the shape of
+//! its gloss ("to depart abruptly" defines a verb, "a person who ..." a
+//! noun). Closed-class words come from fixed lists; open-class words are
+//! tagged by suffix, then corrected from their left neighbour, and
+//! default to nouns.
+
+use std::fmt;
+
+const DETERMINERS: &[&str] = &[
+    "a", "an", "the", "this", "that", "these", "those", "some", "any", "each", "every", "no",
+    "another", "such", "all", "both", "either", "neither", "his", "her", "its", "their", "our",
+    "your", "my",
+];
+const PRONOUNS: &[&str] = &[
+    "i",
+    "you",
+    "he",
+    "she",
+    "it",
+    "we",
+    "they",
+    "me",
+    "him",
+    "us",
+    "them",
+    "one",
+    "someone",
+    "something",
+    "anyone",
+    "anything",
+    "everyone",
+    "everything",
+    "who",
+    "whom",
+    "whose",
+    "which",
+    "what",
+    "oneself",
+    "itself",
+    "himself",
+    "herself",
+    "themselves",
+];
+const PREPOSITIONS: &[&str] = &[
+    "of", "in", "on", "at", "by", "for", "with", "from", "to", "into", "onto", "upon", "about",
+    "over", "under", "between", "through", "during", "without", "within", "against", "among",
+    "across", "after", "before", "around", "toward", "towards", "off", "out", "like", "as",
+];
+const CONJUNCTIONS: &[&str] = &[
+    "and", "or", "but", "nor", "yet", "because", "although", "though", "while", "if", "unless",
+    "whereas", "than",
+];
+/// Auxiliaries and modals, tagged as verbs.
+const AUXILIARIES: &[&str] = &[
+    "is", "are", "was", "were", "be", "been", "being", "am", "has", "have", "had", "do", "does",
+    "did", "can", "could", "may", "might", "must", "shall", "should", "will", "would",
+];
+const ADVERBS: &[&str] = &[
+    "not", "very", "too", "also", "often", "always", "never", "quite", "rather", "almost", "just",
+    "only", "still", "even", "already", "soon", "again", "here", "there", "now", "then", "so",
+];
+/// Common adjectives no suffix rule catches.
+const ADJECTIVES: &[&str] = &[
+    "good",
+    "bad",
+    "new",
+    "old",
+    "young",
+    "long",
+    "short",
+    "small",
+    "large",
+    "big",
+    "great",
+    "high",
+    "low",
+    "kind",
+    "full",
+    "empty",
+    "easy",
+    "hard",
+    "real",
+    "true",
+    "false",
+    "same",
+    "other",
+    "different",
+    "askew",
+    "awry",
+    "quick",
+    "slow",
+    "strong",
+    "weak",
+    "wide",
+    "narrow",
+];
+/// Words ending in "-ly" that are not adverbs.
+const LY_NON_ADVERBS: &[(&str, PosTag)] = &[
+    ("family", PosTag::Noun),
+    ("belly", PosTag::Noun),
+    ("jelly", PosTag::Noun),
+    ("ally", PosTag::Noun),
+    ("rally", PosTag::Noun),
+    ("bully", PosTag::Noun),
+    ("reply", PosTag::Verb),
+    ("supply", PosTag::Verb),
+    ("apply", PosTag::Verb),
+    ("early", PosTag::Adjective),
+    ("friendly", PosTag::Adjective),
+    ("lovely", PosTag::Adjective),
+    ("ugly", PosTag::Adjective),
+    ("holy", PosTag::Adjective),
+    ("likely", PosTag::Adjective),
+    ("daily", PosTag::Adjective),
+];
+
+const NOUN_SUFFIXES: &[&str] = &[
+    "tion", "sion", "ment", "ness", "ity", "ism", "ist", "ance", "ence", "ship", "hood", "dom",
+    "ure", "age", "logy", "er", "or",
+];
+const ADJECTIVE_SUFFIXES: &[&str] = &[
+    "ous", "ful", "ive", "able", "ible", "ical", "al", "ic", "less", "ish", "ary", "ory", "ant",
+    "ent",
+];
+const VERB_SUFFIXES: &[&str] = &["ize", "ise", "ify", "ate"];
+
+/// Openings of glosses that define adjectives.
+const ADJECTIVE_OPENINGS: &[&str] = &[
+    "of or relating to",
+    "relating to",
+    "pertaining to",
+    "characterized by",
+    "characterised by",
+    "having",
+    "full of",
+    "fond of",
+    "marked by",
+    "resembling",
+    "capable of",
+    "able to",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PosTag {
+    Noun,
+    Verb,
+    Adjective,
+    Adverb,
+    Pronoun,
+    Determiner,
+    Preposition,
+    Conjunction,
+}
+
+impl PosTag {
+    pub const ALL: [PosTag; 8] = [
+        PosTag::Noun,
+        PosTag::Verb,
+        PosTag::Adjective,
+        PosTag::Adverb,
+        PosTag::Pronoun,
+        PosTag::Determiner,
+        PosTag::Preposition,
+        PosTag::Conjunction,
+    ];
+
+    /// The parts of speech a headword can have.
+    pub const OPEN: [PosTag; 4] = [
+        PosTag::Noun,
+        PosTag::Verb,
+        PosTag::Adjective,
+        PosTag::Adverb,
+    ];
+
+    /// Position in [`PosTag::ALL`]. The open classes come first, so for them
+    /// it is also the position in [`PosTag::OPEN`].
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PosTag::Noun => "noun",
+            PosTag::Verb => "verb",
+            PosTag::Adjective => "adjective",
+            PosTag::Adverb => "adverb",
+            PosTag::Pronoun => "pronoun",
+            PosTag::Determiner => "determiner",
+            PosTag::Preposition => "preposition",
+            PosTag::Conjunction => "conjunction",
+        }
+    }
+
+    /// Reads the part-of-speech labels of dictionary files and Kaikki
+    /// extracts ("noun", "adj", "adv", ...); labels with no open-class
+    /// equivalent give `None`.
+    pub fn from_dictionary_label(label: &str) -> Option<Self> {
+        match label.trim().to_lowercase().as_str() {
+            "noun" | "n" | "name" | "proper noun" => Some(PosTag::Noun),
+            "verb" | "v" => Some(PosTag::Verb),
+            "adjective" | "adj" | "a" => Some(PosTag::Adjective),
+            "adverb" | "adv" | "r" => Some(PosTag::Adverb),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PosTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// One tag per word of `words` (lowercase, as written).
+pub fn tag(words: &[String]) -> Vec<PosTag> {
+    let mut tags: Vec<PosTag> = Vec::with_capacity(words.len());
+    for (index, word) in words.iter().enumerate() {
+        let previous = index
+            .checked_sub(1)
+            .map(|before| (&words[before], tags[before]));
+        tags.push(tag_word(word, previous));
+    }
+    tags
+}
+
+fn tag_word(word: &str, previous: Option<(&String, PosTag)>) -> PosTag {
+    if let Some(tag) = closed_class(word) {
+        return tag;
+    }
+    let Some(guess) = open_class(word) else {
+        // An unknown word after "to" or an auxiliary is a verb ("to depart",
+        // "will abscond"), otherwise a noun.
+        return match previous {
+            Some((before, _)) if before == "to" || AUXILIARIES.contains(&before.as_str()) => {
+                PosTag::Verb
+            }
+            _ => PosTag::Noun,
+        };
+    };
+    match previous {
+        // "an established church", "the throwing"
+        Some((_, PosTag::Determiner | PosTag::Adjective))
+            if guess == PosTag::Verb && (word.ends_with("ed") || word.ends_with("ing")) =>
+        {
+            PosTag::Adjective
+        }
+        _ => guess,
+    }
+}
+
+fn closed_class(word: &str) -> Option<PosTag> {
+    let lists: [(&[&str], PosTag); 7] = [
+        (DETERMINERS, PosTag::Determiner),
+        (PRONOUNS, PosTag::Pronoun),
+        (PREPOSITIONS, PosTag::Preposition),
+        (CONJUNCTIONS, PosTag::Conjunction),
+        (AUXILIARIES, PosTag::Verb),
+        (ADVERBS, PosTag::Adverb),
+        (ADJECTIVES, PosTag::Adjective),
+    ];
+    lists
+        .iter()
+        .find(|(list, _)| list.contains(&word))
+        .map(|(_, tag)| *tag)
+}
+
+/// Tags an open-class word by its suffix; `None` when no rule applies.
+fn open_class(word: &str) -> Option<PosTag> {
+    if let Some((_, tag)) = LY_NON_ADVERBS.iter().find(|(form, _)| *form == word) {
+        return Some(*tag);
+    }
+    if word.len() < 4 {
+        return None;
+    }
+    if word.ends_with("ly") {
+        return Some(PosTag::Adverb);
+    }
+    if word.ends_with("ing") || word.ends_with("ed") {
+        return Some(PosTag::Verb);
+    }
+    // The longest matching suffix decides, so "-ical" beats "-al".
+    [
+        (NOUN_SUFFIXES, PosTag::Noun),
+        (ADJECTIVE_SUFFIXES, PosTag::Adjective),
+        (VERB_SUFFIXES, PosTag::Verb),
+    ]
+    .iter()
+    .flat_map(|(suffixes, tag)| {
+        suffixes
+            .iter()
+            .filter(|suffix| word.ends_with(*suffix))
+            .map(move |suffix| (suffix.len(), *tag))
+    })
+    .max_by_key(|(length, _)| *length)
+    .map(|(_, tag)| tag)
+}
+
+/// The part of speech of the headword a gloss defines, from its opening:
+/// "to" + verb for verbs, a determiner or pronoun for nouns, stock phrases
+/// such as "relating to" for adjectives, "in a ... way/manner" for adverbs,
+/// and otherwise the tag of the first word, a gerund counting as a noun.
+pub fn headword_pos(words: &[String], tags: &[PosTag]) -> Option<PosTag> {
+    let first = words.first()?;
+    let opening = words.join(" ");
+    if ADJECTIVE_OPENINGS
+        .iter()
+        .any(|phrase| opening == *phrase || opening.starts_with(&format!("{phrase} ")))
+    {
+        return Some(PosTag::Adjective);
+    }
+    if first == "to" && tags.get(1) == Some(&PosTag::Verb) {
+        return Some(PosTag::Verb);
+    }
+    if first == "in"
+        && matches!(tags.get(1), Some(PosTag::Determiner))
+        && words
+            .iter()
+            .take(5)
+            .any(|word| word == "way" || word == "manner")
+    {
+        return Some(PosTag::Adverb);
+    }
+    Some(match tags[0] {
+        PosTag::Determiner | PosTag::Pronoun | PosTag::Noun => PosTag::Noun,
+        PosTag::Verb if first.ends_with("ing") => PosTag::Noun,
+        PosTag::Verb => PosTag::Verb,
+        PosTag::Adjective => PosTag::Adjective,
+        PosTag::Adverb => PosTag::Adverb,
+        PosTag::Preposition | PosTag::Conjunction => PosTag::Adjective,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn headword(gloss: &str) -> Option<PosTag> {
+        let words = words(gloss);
+        headword_pos(&words, &tag(&words))
+    }
+
+    #[test]
+    fn words_are_tagged_from_lists_suffixes_and_neighbours() {
+        assert_eq!(
+            tag(&words("to depart abruptly")),
+            [PosTag::Preposition, PosTag::Verb, PosTag::Adverb]
+        );
+        assert_eq!(
+            tag(&words("the quality of being kind")),
+            [
+                PosTag::Determiner,
+                PosTag::Noun,
+                PosTag::Preposition,
+                PosTag::Verb,
+                PosTag::Adjective
+            ]
+        );
+    }
+
+    #[test]
+    fn glosses_reveal_the_headword_class() {
+        assert_eq!(headword("to depart abruptly"), Some(PosTag::Verb));
+        assert_eq!(headword("the quality of being kind"), Some(PosTag::Noun));
+        assert_eq!(
+            headword("of or relating to family"),
+            Some(PosTag::Adjective)
+        );
+        assert_eq!(headword("in a kind way"), Some(PosTag::Adverb));
+        assert_eq!(headword(""), None);
+    }
+
+    #[test]
+    fn dictionary_labels_map_to_open_classes() {
+        assert_eq!(
+            PosTag::from_dictionary_label(" Adj "),
+            Some(PosTag::Adjective)
+        );
+        assert_eq!(
+            PosTag::from_dictionary_label("proper noun"),
+            Some(PosTag::Noun)
+        );
+        assert_eq!(PosTag::from_dictionary_label("interjection"), None);
+    }
+
+    #[test]
+    fn tags_index_their_tables() {
+        for (position, tag) in PosTag::ALL.iter().enumerate() {
+            assert_eq!(tag.index(), position);
+        }
+        for (position, tag) in PosTag::OPEN.iter().enumerate() {
+            assert_eq!(tag.index(), position);
+        }
+    }
+}
@@ -0,0 +1,442 @@
+//! Per-dimension scaling of definition feature vectors (`--scale`), so that
+//! a count such as `basic.word_count` does not swamp 0–1 ratios in any
+//! distance. Statistics are fitted on every loaded definition and can be
+//! saved with `--save-scaler` and reloaded with `--scaler`, so later runs
+//! transform new words exactly like the corpus they were fitted on.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::features::SparseVector;
+
+const HEADER: &str = "# erebus scaling statistics";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalingMethod {
+    /// Subtract the mean, divide by the standard deviation.
+    ZScore,
+    /// Map the fitted minimum to 0 and maximum to 1.
+    MinMax,
+    /// Divide by the dimension's L2 norm over the corpus.
+    L2,
+    /// Replace a value by its percentile rank among the fitted values.
+    Rank,
+}
+
+impl ScalingMethod {
+    pub fn label(self) -> &'static str {
+        match self {
+            ScalingMethod::ZScore => "zscore",
+            ScalingMethod::MinMax => "minmax",
+            ScalingMethod::L2 => "l2",
+            ScalingMethod::Rank => "rank",
+        }
+    }
+}
+
+impl fmt::Display for ScalingMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl FromStr for ScalingMethod {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "zscore" => Ok(ScalingMethod::ZScore),
+            "minmax" => Ok(ScalingMethod::MinMax),
+            "l2" => Ok(ScalingMethod::L2),
+            "rank" => Ok(ScalingMethod::Rank),
+            other => Err(format!(
+                "unknown scaling '{other}' (expected 'zscore', 'minmax', 'l2' or 'rank')"
+            )),
+        }
+    }
+}
+
+/// What one dimension was fitted to.
+#[derive(Debug, Clone, PartialEq)]
+enum Column {
+    ZScore {
+        mean: f32,
+        std_dev: f32,
+    },
+    MinMax {
+        min: f32,
+        max: f32,
+    },
+    L2 {
+        norm: f32,
+    },
+    /// The distinct fitted values in increasing order, each with its
+    /// percentile rank in [0, 1] (ties share their mean rank).
+    Rank(Vec<(f32, f32)>),
+}
+
+impl Column {
+    /// Fits a column from its non-zero values out of `rows` vectors.
+    fn fit(method: ScalingMethod, mut values: Vec<f32>, rows: usize) -> Self {
+        let zeros = rows - values.len();
+        match method {
+            // Nothing to fit; the column scales everything to 0.
+            ScalingMethod::ZScore if rows == 0 => Column::ZScore {
+                mean: 0.0,
+                std_dev: 0.0,
+            },
+            ScalingMethod::ZScore => {
+                let mean = sum(values.iter().copied()) / rows as f32;
+                let squares = sum(values.iter().map(|value| (value - mean).powi(2)))
+                    + zeros as f32 * mean * mean;
+                Column::ZScore {
+                    mean,
+                    std_dev: (squares / rows as f32).sqrt(),
+                }
+            }
+            ScalingMethod::MinMax => {
+                if zeros > 0 {
+                    values.push(0.0);
+                }
+                Column::MinMax {
+                    min: values.iter().copied().fold(f32::INFINITY, f32::min),
+                    max: values.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+                }
+            }
+            ScalingMethod::L2 => Column::L2 {
+                norm: sum(values.iter().map(|value| value * value)).sqrt(),
+            },
+            ScalingMethod::Rank => {
+                values.extend(std::iter::repeat_n(0.0, zeros));
+                values.sort_by(f32::total_cmp);
+                let last = rows.saturating_sub(1).max(1) as f32;
+                let mut ranks = Vec::new();
+                let mut below = 0;
+                for group in values.chunk_by(|a, b| a == b) {
+                    let mean_rank = below as f32 + (group.len() - 1) as f32 / 2.0;
+                    let percentile = if rows == 1 { 0.5 } else { mean_rank / last };
+                    ranks.push((group[0], percentile));
+                    below += group.len();
+                }
+                Column::Rank(ranks)
+            }
+        }
+    }
+
+    /// Constant dimensions scale to 0 (to 0.5 under `rank`, where every
+    /// value ties).
+    fn apply(&self, value: f32) -> f32 {
+        match self {
+            Column::ZScore { mean, std_dev } if *std_dev > 0.0 => (value - mean) / std_dev,
+            Column::MinMax { min, max } if max > min => (value - min) / (max - min),
+            Column::L2 { norm } if *norm > 0.0 => value / norm,
+            Column::Rank(ranks) if !ranks.is_empty() => percentile(ranks, value),
+            _ => 0.0,
+        }
+    }
+
+    fn to_fields(&self) -> Vec<String> {
+        match self {
+            Column::ZScore { mean, std_dev } => vec![mean.to_string(), std_dev.to_string()],
+            Column::MinMax { min, max } => vec![min.to_string(), max.to_string()],
+            Column::L2 { norm } => vec![norm.to_string()],
+            Column::Rank(ranks) => ranks
+                .iter()
+                .map(|(value, rank)| format!("{value}:{rank}"))
+                .collect(),
+        }
+    }
+
+    fn from_fields(method: ScalingMethod, fields: &[&str]) -> Option<Self> {
+        let numbers =
+            || -> Option<Vec<f32>> { fields.iter().map(|field| field.parse().ok()).collect() };
+        match method {
+            ScalingMethod::ZScore => match numbers()?[..] {
+                [mean, std_dev] => Some(Column::ZScore { mean, std_dev }),
+                _ => None,
+            },
+            ScalingMethod::MinMax => match numbers()?[..] {
+                [min, max] => Some(Column::MinMax { min, max }),
+                _ => None,
+            },
+            ScalingMethod::L2 => match numbers()?[..] {
+                [norm] => Some(Column::L2 { norm }),
+                _ => None,
+            },
+            ScalingMethod::Rank => fields
+                .iter()
+                .map(|field| {
+                    let (value, rank) = field.split_once(':')?;
+                    Some((value.parse().ok()?, rank.parse().ok()?))
+                })
+                .collect::<Option<Vec<_>>>()
+                .filter(|ranks| ranks.windows(2).all(|pair| pair[0].0 < pair[1].0))
+                .map(Column::Rank),
+        }
+    }
+}
+
+/// Sums from +0, so that an empty column saves as `0` rather than `-0`.
+fn sum(values: impl Iterator<Item = f32>) -> f32 {
+    values.fold(0.0, |total, value| total + value)
+}
+
+/// The percentile of `value` among the fitted values: exact for a fitted
+/// value, interpolated between its neighbours otherwise, and 0 or 1 beyond
+/// the fitted range.
+fn percentile(ranks: &[(f32, f32)], value: f32) -> f32 {
+    match ranks.binary_search_by(|(fitted, _)| fitted.total_cmp(&value)) {
+        Ok(index) => ranks[index].1,
+        Err(0) => 0.0,
+        Err(index) if index == ranks.len() => 1.0,
+        Err(index) => {
+            let (low, low_rank) = ranks[index - 1];
+            let (high, high_rank) = ranks[index];
+            low_rank + (high_rank - low_rank) * (value - low) / (high - low)
+        }
+    }
+}
+
+/// Fitted per-dimension statistics for one scaling method.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scaler {
+    method: ScalingMethod,
+    dimensions: Vec<String>,
+    columns: Vec<Column>,
+    /// What an implicit zero becomes in each column where that is not zero,
+    /// so `transform` touches only stored entries and these.
+    zero_images: Vec<(usize, f32)>,
+}
+
+impl Scaler {
+    /// Fits every dimension in `dimensions` on `vectors`.
+    pub fn fit(method: ScalingMethod, dimensions: Vec<String>, vectors: &[SparseVector]) -> Self {
+        let mut values = vec![Vec::new(); dimensions.len()];
+        for vector in vectors {
+            for &(index, value) in vector.entries() {
+                values[index].push(value);
+            }
+        }
+        let columns = values
+            .into_iter()
+            .map(|column| Column::fit(method, column, vectors.len()))
+            .collect();
+        Self::new(method, dimensions, columns)
+    }
+
+    fn new(method: ScalingMethod, dimensions: Vec<String>, columns: Vec<Column>) -> Self {
+        let zero_images = columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| (index, column.apply(0.0)))
+            .filter(|&(_, image)| image != 0.0)
+            .collect();
+        Self {
+            method,
+            dimensions,
+            columns,
+            zero_images,
+        }
+    }
+
+    pub fn method(&self) -> ScalingMethod {
+        self.method
+    }
+
+    pub fn dimensions(&self) -> &[String] {
+        &self.dimensions
+    }
+
+    /// Scales the stored entries of `vector` and fills in the image of zero
+    /// for the columns it leaves implicit. `minmax` and `l2` keep
+    /// non-negative sparse columns sparse; `zscore` and `rank` move zero
+    /// away from 0 and so fill every column that was fitted on non-zero
+    /// values.
+    pub fn transform(&self, vector: &SparseVector) -> SparseVector {
+        let stored = vector.entries();
+        let mut entries: Vec<(usize, f32)> = stored
+            .iter()
+            .map(|&(index, value)| (index, self.columns[index].apply(value)))
+            .collect();
+        entries.extend(self.zero_images.iter().copied().filter(|(index, _)| {
+            stored
+                .binary_search_by_key(index, |&(stored, _)| stored)
+                .is_err()
+        }));
+        SparseVector::from_entries(entries)
+    }
+
+    /// Writes a header, a `method<TAB>name` line, then one
+    /// `dimension<TAB>statistic...` line per dimension; rank statistics are
+    /// `value:percentile` pairs.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut out = format!("{HEADER}\nmethod\t{}\n", self.method);
+        for (dimension, column) in self.dimensions.iter().zip(&self.columns) {
+            out.push_str(dimension);
+            for field in column.to_fields() {
+                out.push('\t');
+                out.push_str(&field);
+            }
+            out.push('\n');
+        }
+        fs::write(path, out)
+            .map_err(|err| format!("cannot write scaling statistics {}: {err}", path.display()))?;
+        Ok(())
+    }
+
+    /// Loads statistics written by [`Scaler::save`].
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read scaling statistics {}: {err}", path.display()))?;
+        let mut method = None;
+        let mut dimensions = Vec::new();
+        let mut columns = Vec::new();
+        for (index, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let Some(method) = method else {
+                method = match fields[..] {
+                    ["method", name] => Some(
+                        name.parse::<ScalingMethod>()
+                            .map_err(|err| format!("{}:{}: {err}", path.display(), index + 1))?,
+                    ),
+                    _ => {
+                        return Err(format!(
+                            "{}:{}: expected method<TAB>name",
+                            path.display(),
+                            index + 1
+                        )
+                        .into());
+                    }
+                };
+                continue;
+            };
+            let column = Column::from_fields(method, &fields[1..]).ok_or_else(|| {
+                format!(
+                    "{}:{}: malformed {method} statistics",
+                    path.display(),
+                    index + 1
+                )
+            })?;
+            dimensions.push(fields[0].to_string());
+            columns.push(column);
+        }
+        let method = method.ok_or_else(|| format!("{}: no scaling method", path.display()))?;
+        Ok(Self::new(method, dimensions, columns))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Three definitions over three dimensions; the last is never set.
+    fn fitted(method: ScalingMethod) -> Scaler {
+        let vectors = [
+            SparseVector::from_entries(vec![(0, 2.0)]),
+            SparseVector::from_entries(vec![(0, 4.0), (1, 1.0)]),
+            SparseVector::default(),
+        ];
+        let dimensions = ["a", "b", "c"].map(str::to_string).to_vec();
+        Scaler::fit(method, dimensions, &vectors)
+    }
+
+    fn assert_entries(vector: &SparseVector, expected: &[(usize, f32)]) {
+        assert_eq!(vector.entries().len(), expected.len(), "{vector:?}");
+        for (&(index, value), &(want_index, want)) in vector.entries().iter().zip(expected) {
+            assert_eq!(index, want_index, "{vector:?}");
+            assert!((value - want).abs() < 1e-4, "{vector:?}");
+        }
+    }
+
+    #[test]
+    fn zscore_counts_implicit_zeros() {
+        let scaler = fitted(ScalingMethod::ZScore);
+        // Column a is 2, 4, 0: mean 2, deviation sqrt(8/3). Column b is 1, 0,
+        // 0: mean 1/3, deviation sqrt(2/9). Column c never varies.
+        assert_entries(
+            &scaler.transform(&SparseVector::from_entries(vec![(0, 4.0), (2, 5.0)])),
+            &[(0, 1.2247), (1, -std::f32::consts::FRAC_1_SQRT_2)],
+        );
+        assert_entries(
+            &scaler.transform(&SparseVector::default()),
+            &[(0, -1.2247), (1, -std::f32::consts::FRAC_1_SQRT_2)],
+        );
+    }
+
+    #[test]
+    fn minmax_and_l2_keep_sparse_columns_sparse() {
+        let scaler = fitted(ScalingMethod::MinMax);
+        assert!(scaler.zero_images.is_empty());
+        assert_entries(
+            &scaler.transform(&SparseVector::from_entries(vec![(0, 2.0), (1, 1.0)])),
+            &[(0, 0.5), (1, 1.0)],
+        );
+
+        let scaler = fitted(ScalingMethod::L2);
+        assert!(scaler.zero_images.is_empty());
+        assert_entries(
+            &scaler.transform(&SparseVector::from_entries(vec![(0, 4.0)])),
+            &[(0, 0.8944)],
+        );
+        assert_entries(&scaler.transform(&SparseVector::default()), &[]);
+    }
+
+    #[test]
+    fn rank_ties_share_their_mean_rank_and_interpolate() {
+        let scaler = fitted(ScalingMethod::Rank);
+        // Column a ranks 0, 2, 4 at 0, 0.5, 1; column b's two zeros tie at
+        // 0.25; column c is all ties at 0.5.
+        assert_entries(
+            &scaler.transform(&SparseVector::from_entries(vec![(0, 3.0), (1, 9.0)])),
+            &[(0, 0.75), (1, 1.0), (2, 0.5)],
+        );
+        assert_entries(
+            &scaler.transform(&SparseVector::default()),
+            &[(1, 0.25), (2, 0.5)],
+        );
+    }
+
+    #[test]
+    fn no_definitions_fit_constant_columns() {
+        let dimensions = vec!["a".to_string()];
+        for method in [
+            ScalingMethod::ZScore,
+            ScalingMethod::MinMax,
+            ScalingMethod::L2,
+            ScalingMethod::Rank,
+        ] {
+            let scaler = Scaler::fit(method, dimensions.clone(), &[]);
+            let scaled = scaler.transform(&SparseVector::from_entries(vec![(0, 3.0)]));
+            assert!(
+                scaled.entries().iter().all(|(_, value)| value.is_finite()),
+                "{method}: {scaled:?}"
+            );
+        }
+        let scaler = Scaler::fit(ScalingMethod::ZScore, dimensions, &[]);
+        assert_eq!(scaler.columns[0].to_fields(), ["0", "0"]);
+    }
+
+    #[test]
+    fn saved_statistics_load_back_unchanged() {
+        for method in [
+            ScalingMethod::ZScore,
+            ScalingMethod::MinMax,
+            ScalingMethod::L2,
+            ScalingMethod::Rank,
+        ] {
+            let scaler = fitted(method);
+            let path = std::env::temp_dir()
+                .join(format!("erebus-{}-unit-{method}.tsv", std::process::id()));
+            scaler.save(&path).expect("statistics are saved");
+            let loaded = Scaler::load(&path).expect("saved statistics load");
+            fs::remove_file(&path).ok();
+            assert_eq!(loaded, scaler);
+        }
+    }
+}
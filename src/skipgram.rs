@@ -0,0 +1,191 @@
+//! A small word2vec: skip-gram with negative sampling (Mikolov et al.,
+//! 2013) trained on the definition text itself, for the `skipgram` feature
+//! extractor. Single-threaded and driven by one seeded generator, so the
+//! same corpus and seed always give the same vectors.
+
+use std::collections::HashMap;
+
+use crate::rng::SplitMix64;
+
+/// Negative samples are drawn from unigram counts raised to this power.
+const UNIGRAM_POWER: f64 = 0.75;
+/// The learning rate decays linearly to this fraction of its start.
+const MIN_LEARNING_RATE_FRACTION: f32 = 1e-4;
+/// Scores beyond ±this are treated as saturated.
+const MAX_SCORE: f32 = 6.0;
+
+#[derive(Debug, Clone)]
+pub struct SkipGramConfig {
+    pub dimensions: usize,
+    /// Largest distance between a centre word and a context word; the
+    /// actual window is drawn uniformly from `1..=window` per position.
+    pub window: usize,
+    pub negatives: usize,
+    pub epochs: usize,
+    pub learning_rate: f32,
+    pub seed: u64,
+}
+
+impl Default for SkipGramConfig {
+    fn default() -> Self {
+        Self {
+            dimensions: 32,
+            window: 5,
+            negatives: 5,
+            epochs: 5,
+            learning_rate: 0.025,
+            seed: 0,
+        }
+    }
+}
+
+/// Trained word vectors.
+#[derive(Debug, Clone, Default)]
+pub struct SkipGram {
+    index: HashMap<String, usize>,
+    vectors: Vec<Vec<f32>>,
+}
+
+impl SkipGram {
+    /// Trains on `sentences`, each a list of tokens. Every token type gets a
+    /// vector.
+    pub fn train(sentences: &[Vec<String>], config: &SkipGramConfig) -> Self {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for token in sentences.iter().flatten() {
+            *counts.entry(token).or_default() += 1;
+        }
+        // Most frequent first, ties alphabetical, so ids do not depend on
+        // hash order.
+        let mut vocabulary: Vec<(&str, usize)> = counts.into_iter().collect();
+        vocabulary.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        let index: HashMap<String, usize> = vocabulary
+            .iter()
+            .enumerate()
+            .map(|(id, (word, _))| (word.to_string(), id))
+            .collect();
+
+        let dimensions = config.dimensions;
+        let mut rng = SplitMix64::new(config.seed);
+        let mut input: Vec<Vec<f32>> = (0..vocabulary.len())
+            .map(|_| {
+                (0..dimensions)
+                    .map(|_| (rng.uniform() as f32 - 0.5) / dimensions as f32)
+                    .collect()
+            })
+            .collect();
+        let mut output = vec![vec![0.0f32; dimensions]; vocabulary.len()];
+        if vocabulary.len() < 2 {
+            return Self {
+                index,
+                vectors: input,
+            };
+        }
+
+        let noise = NoiseDistribution::new(vocabulary.iter().map(|(_, count)| *count));
+        let encoded: Vec<Vec<usize>> = sentences
+            .iter()
+            .map(|sentence| sentence.iter().map(|token| index[token]).collect())
+            .collect();
+        let total_steps = (config.epochs * encoded.iter().map(Vec::len).sum::<usize>()).max(1);
+        let mut step = 0;
+        let mut gradient = vec![0.0f32; dimensions];
+
+        for _ in 0..config.epochs {
+            for sentence in &encoded {
+                for (position, &centre) in sentence.iter().enumerate() {
+                    let progress = step as f32 / total_steps as f32;
+                    let learning_rate =
+                        config.learning_rate * (1.0 - progress).max(MIN_LEARNING_RATE_FRACTION);
+                    step += 1;
+
+                    let reach = 1 + rng.below(config.window.max(1));
+                    let start = position.saturating_sub(reach);
+                    let end = (position + reach + 1).min(sentence.len());
+                    for (offset, &context) in sentence[start..end].iter().enumerate() {
+                        if start + offset == position {
+                            continue;
+                        }
+                        gradient.iter_mut().for_each(|value| *value = 0.0);
+                        let targets = std::iter::once((context, 1.0)).chain(
+                            (0..config.negatives)
+                                .map(|_| noise.sample(&mut rng))
+                                .filter(|&word| word != context)
+                                .map(|word| (word, 0.0))
+                                .collect::<Vec<_>>(),
+                        );
+                        for (target, label) in targets {
+                            let score = dot(&input[centre], &output[target]);
+                            let predicted = sigmoid(score);
+                            let delta = (label - predicted) * learning_rate;
+                            for ((slot, out), inp) in gradient
+                                .iter_mut()
+                                .zip(output[target].iter_mut())
+                                .zip(&input[centre])
+                            {
+                                *slot += delta * *out;
+                                *out += delta * inp;
+                            }
+                        }
+                        for (value, change) in input[centre].iter_mut().zip(&gradient) {
+                            *value += change;
+                        }
+                    }
+                }
+            }
+        }
+
+        Self {
+            index,
+            vectors: input,
+        }
+    }
+
+    /// Number of words with a vector.
+    pub fn len(&self) -> usize {
+        self.vectors.len()
+    }
+
+    pub fn vector(&self, word: &str) -> Option<&[f32]> {
+        self.index.get(word).map(|&id| self.vectors[id].as_slice())
+    }
+}
+
+/// Samples word ids in proportion to `count ^ UNIGRAM_POWER`.
+struct NoiseDistribution {
+    cumulative: Vec<f64>,
+}
+
+impl NoiseDistribution {
+    fn new(counts: impl Iterator<Item = usize>) -> Self {
+        let mut total = 0.0;
+        let cumulative = counts
+            .map(|count| {
+                total += (count as f64).powf(UNIGRAM_POWER);
+                total
+            })
+            .collect();
+        Self { cumulative }
+    }
+
+    fn sample(&self, rng: &mut SplitMix64) -> usize {
+        let total = self.cumulative.last().copied().unwrap_or(0.0);
+        let target = rng.uniform() * total;
+        self.cumulative
+            .partition_point(|&bound| bound <= target)
+            .min(self.cumulative.len() - 1)
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn sigmoid(score: f32) -> f32 {
+    if score > MAX_SCORE {
+        1.0
+    } else if score < -MAX_SCORE {
+        0.0
+    } else {
+        1.0 / (1.0 + (-score).exp())
+    }
+}
@@ -0,0 +1,323 @@
+//! The Porter2 ("English" Snowball) stemmer of Martin Porter, for the `stem`
+//! normalizer. Works on lowercase ASCII words, which is all the tokenizer
+//! produces, so the apostrophe handling of the full algorithm is not needed.
+
+/// Words whose stem the rules would get wrong.
+const EXCEPTIONS: [(&str, &str); 18] = [
+    ("skis", "ski"),
+    ("skies", "sky"),
+    ("dying", "die"),
+    ("lying", "lie"),
+    ("tying", "tie"),
+    ("idly", "idl"),
+    ("gently", "gentl"),
+    ("ugly", "ugli"),
+    ("early", "earli"),
+    ("only", "onli"),
+    ("singly", "singl"),
+    ("sky", "sky"),
+    ("news", "news"),
+    ("howe", "howe"),
+    ("atlas", "atlas"),
+    ("cosmos", "cosmos"),
+    ("bias", "bias"),
+    ("andes", "andes"),
+];
+
+/// Words left alone once step 1a has run.
+const STEP_1A_INVARIANTS: [&str; 8] = [
+    "inning", "outing", "canning", "herring", "earring", "proceed", "exceed", "succeed",
+];
+
+/// Prefixes after which R1 starts, overriding the usual rule.
+const R1_PREFIXES: [&str; 3] = ["gener", "commun", "arsen"];
+
+const STEP_2: [(&str, &str); 24] = [
+    ("tional", "tion"),
+    ("enci", "ence"),
+    ("anci", "ance"),
+    ("abli", "able"),
+    ("entli", "ent"),
+    ("izer", "ize"),
+    ("ization", "ize"),
+    ("ational", "ate"),
+    ("ation", "ate"),
+    ("ator", "ate"),
+    ("alism", "al"),
+    ("aliti", "al"),
+    ("alli", "al"),
+    ("fulness", "ful"),
+    ("ousli", "ous"),
+    ("ousness", "ous"),
+    ("iveness", "ive"),
+    ("iviti", "ive"),
+    ("biliti", "ble"),
+    ("bli", "ble"),
+    ("ogi", "og"),
+    ("fulli", "ful"),
+    ("lessli", "less"),
+    ("li", ""),
+];
+
+const STEP_3: [(&str, &str); 9] = [
+    ("tional", "tion"),
+    ("ational", "ate"),
+    ("alize", "al"),
+    ("icate", "ic"),
+    ("iciti", "ic"),
+    ("ical", "ic"),
+    ("ful", ""),
+    ("ness", ""),
+    ("ative", ""),
+];
+
+const STEP_4: [&str; 18] = [
+    "al", "ance", "ence", "er", "ic", "able", "ible", "ant", "ement", "ment", "ent", "ism", "ate",
+    "iti", "ous", "ive", "ize", "ion",
+];
+
+/// Letters that may precede a deleted `li`.
+const LI_ENDINGS: &[u8] = b"cdeghkmnrt";
+const DOUBLES: [&[u8]; 9] = [
+    b"bb", b"dd", b"ff", b"gg", b"mm", b"nn", b"pp", b"rr", b"tt",
+];
+
+/// The Porter2 stem of a lowercase word. Words of one or two letters and
+/// words with anything but ASCII letters are returned unchanged.
+pub fn stem(word: &str) -> String {
+    if word.len() <= 2 || !word.bytes().all(|byte| byte.is_ascii_lowercase()) {
+        return word.to_string();
+    }
+    if let Some((_, stem)) = EXCEPTIONS.iter().find(|(form, _)| *form == word) {
+        return stem.to_string();
+    }
+
+    let mut word = Word::new(word);
+    word.step_1a();
+    if STEP_1A_INVARIANTS.contains(&word.as_str()) {
+        return word.finish();
+    }
+    word.step_1b();
+    word.step_1c();
+    word.step_2();
+    word.step_3();
+    word.step_4();
+    word.step_5();
+    word.finish()
+}
+
+/// A word being stemmed. A `y` that acts as a consonant is held as `Y`.
+struct Word {
+    letters: Vec<u8>,
+    r1: usize,
+    r2: usize,
+}
+
+impl Word {
+    fn new(word: &str) -> Self {
+        let mut letters = word.as_bytes().to_vec();
+        for index in 0..letters.len() {
+            if letters[index] == b'y' && (index == 0 || is_vowel(letters[index - 1])) {
+                letters[index] = b'Y';
+            }
+        }
+        let r1 = R1_PREFIXES
+            .iter()
+            .find(|prefix| letters.starts_with(prefix.as_bytes()))
+            .map_or_else(|| region_start(&letters, 0), |prefix| prefix.len());
+        let r2 = region_start(&letters, r1);
+        Self { letters, r1, r2 }
+    }
+
+    fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.letters).expect("stems are ASCII")
+    }
+
+    fn finish(self) -> String {
+        self.as_str().replace('Y', "y")
+    }
+
+    fn ends_with(&self, suffix: &str) -> bool {
+        self.letters.ends_with(suffix.as_bytes())
+    }
+
+    /// The longest of `suffixes` the word ends with.
+    fn longest<'a>(&self, suffixes: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+        suffixes
+            .filter(|suffix| self.ends_with(suffix))
+            .max_by_key(|suffix| suffix.len())
+    }
+
+    fn in_r1(&self, suffix: &str) -> bool {
+        self.letters.len() - suffix.len() >= self.r1
+    }
+
+    fn in_r2(&self, suffix: &str) -> bool {
+        self.letters.len() - suffix.len() >= self.r2
+    }
+
+    fn replace(&mut self, suffix: &str, replacement: &str) {
+        self.letters.truncate(self.letters.len() - suffix.len());
+        self.letters.extend_from_slice(replacement.as_bytes());
+    }
+
+    fn has_vowel(&self, end: usize) -> bool {
+        self.letters[..end].iter().any(|&letter| is_vowel(letter))
+    }
+
+    /// Whether the word ends in a short syllable: a vowel followed by a
+    /// non-vowel other than `w`, `x` or `Y` and preceded by a non-vowel, or
+    /// a vowel and a non-vowel making up the whole word.
+    fn ends_in_short_syllable(&self) -> bool {
+        match self.letters[..] {
+            [first, second] => is_vowel(first) && !is_vowel(second),
+            [.., before, vowel, after] => {
+                !is_vowel(before)
+                    && is_vowel(vowel)
+                    && !is_vowel(after)
+                    && !matches!(after, b'w' | b'x' | b'Y')
+            }
+            _ => false,
+        }
+    }
+
+    fn is_short(&self) -> bool {
+        self.r1 >= self.letters.len() && self.ends_in_short_syllable()
+    }
+
+    /// Plurals and `-ied`/`-ies`.
+    fn step_1a(&mut self) {
+        let suffixes = ["sses", "ied", "ies", "us", "ss", "s"];
+        match self.longest(suffixes.into_iter()) {
+            Some("sses") => self.replace("sses", "ss"),
+            Some(suffix @ ("ied" | "ies")) => {
+                let replacement = if self.letters.len() - suffix.len() > 1 {
+                    "i"
+                } else {
+                    "ie"
+                };
+                self.replace(suffix, replacement);
+            }
+            Some("s") => {
+                let before = self.letters.len() - 2;
+                if self.has_vowel(before) {
+                    self.replace("s", "");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// `-eed`, `-ed`, `-ing` and their `-ly` forms.
+    fn step_1b(&mut self) {
+        let suffixes = ["eed", "eedly", "ed", "edly", "ing", "ingly"];
+        let Some(suffix) = self.longest(suffixes.into_iter()) else {
+            return;
+        };
+        if suffix.starts_with("ee") {
+            if self.in_r1(suffix) {
+                self.replace(suffix, "ee");
+            }
+            return;
+        }
+        if !self.has_vowel(self.letters.len() - suffix.len()) {
+            return;
+        }
+        self.replace(suffix, "");
+        if self.ends_with("at") || self.ends_with("bl") || self.ends_with("iz") {
+            self.letters.push(b'e');
+        } else if DOUBLES.iter().any(|double| self.letters.ends_with(double)) {
+            self.letters.pop();
+        } else if self.is_short() {
+            self.letters.push(b'e');
+        }
+    }
+
+    /// A final `y` after a consonant becomes `i`, unless it follows the first
+    /// letter.
+    fn step_1c(&mut self) {
+        let length = self.letters.len();
+        if matches!(self.letters[length - 1], b'y' | b'Y')
+            && length > 2
+            && !is_vowel(self.letters[length - 2])
+        {
+            self.letters[length - 1] = b'i';
+        }
+    }
+
+    fn step_2(&mut self) {
+        let Some(suffix) = self.longest(STEP_2.iter().map(|(suffix, _)| *suffix)) else {
+            return;
+        };
+        if !self.in_r1(suffix) {
+            return;
+        }
+        let before = self.letters[..self.letters.len() - suffix.len()].last();
+        let allowed = match suffix {
+            "ogi" => before == Some(&b'l'),
+            "li" => before.is_some_and(|letter| LI_ENDINGS.contains(letter)),
+            _ => true,
+        };
+        if allowed {
+            let (_, replacement) = STEP_2.iter().find(|(form, _)| *form == suffix).unwrap();
+            self.replace(suffix, replacement);
+        }
+    }
+
+    fn step_3(&mut self) {
+        let Some(suffix) = self.longest(STEP_3.iter().map(|(suffix, _)| *suffix)) else {
+            return;
+        };
+        if !self.in_r1(suffix) || (suffix == "ative" && !self.in_r2(suffix)) {
+            return;
+        }
+        let (_, replacement) = STEP_3.iter().find(|(form, _)| *form == suffix).unwrap();
+        self.replace(suffix, replacement);
+    }
+
+    fn step_4(&mut self) {
+        let Some(suffix) = self.longest(STEP_4.into_iter()) else {
+            return;
+        };
+        if !self.in_r2(suffix) {
+            return;
+        }
+        if suffix == "ion" {
+            let before = self.letters[..self.letters.len() - 3].last();
+            if !matches!(before, Some(b's' | b't')) {
+                return;
+            }
+        }
+        self.replace(suffix, "");
+    }
+
+    fn step_5(&mut self) {
+        if self.ends_with("e") {
+            let delete = self.in_r2("e") || {
+                self.in_r1("e") && {
+                    self.letters.pop();
+                    let short = self.ends_in_short_syllable();
+                    self.letters.push(b'e');
+                    !short
+                }
+            };
+            if delete {
+                self.letters.pop();
+            }
+        } else if self.ends_with("ll") && self.in_r2("l") {
+            self.letters.pop();
+        }
+    }
+}
+
+fn is_vowel(letter: u8) -> bool {
+    matches!(letter, b'a' | b'e' | b'i' | b'o' | b'u' | b'y')
+}
+
+/// The start of the region after the first non-vowel that follows a vowel,
+/// searching from `from`; the word's length if there is none.
+fn region_start(letters: &[u8], from: usize) -> usize {
+    (from + 1..letters.len())
+        .find(|&index| !is_vowel(letters[index]) && is_vowel(letters[index - 1]))
+        .map_or(letters.len(), |index| index + 1)
+}
@@ -0,0 +1,441 @@
+//! Statistical subword baselines for comparison with the morphological
+//! segmenters: byte-pair encoding and a unigram language model, both trained
+//! on the word list. Neither knows about prefixes or suffixes, so their pieces
+//! are reported as subwords.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::clean_word;
+
+const BPE_HEADER: &str = "# erebus bpe v1";
+const UNIGRAM_HEADER: &str = "# erebus unigram v1";
+/// Pairs and pieces seen fewer times than this are never added, so a short
+/// word list is not simply memorised whole.
+const MIN_FREQUENCY: u64 = 2;
+/// Longest piece considered when seeding the unigram vocabulary.
+const MAX_PIECE_LEN: usize = 16;
+/// Fraction of the prunable unigram vocabulary kept by each pruning round.
+const SHRINK_FACTOR: f64 = 0.75;
+/// EM iterations between pruning rounds.
+const EM_STEPS: usize = 2;
+/// Log-probability penalty, below the rarest piece, for a letter the unigram
+/// model has never seen.
+const UNKNOWN_PENALTY: f64 = 10.0;
+
+/// Byte-pair encoding: starting from single letters, the most frequent
+/// adjacent pair is merged until the vocabulary reaches the requested size.
+#[derive(Debug, Clone, Default)]
+pub struct BpeModel {
+    merges: Vec<(String, String)>,
+    ranks: HashMap<(String, String), usize>,
+}
+
+impl BpeModel {
+    pub fn train<'a>(words: impl IntoIterator<Item = &'a str>, vocab_size: usize) -> Self {
+        let mut corpus: Vec<(Vec<String>, u64)> = word_counts(words)
+            .into_iter()
+            .map(|(word, count)| (word.chars().map(String::from).collect(), count))
+            .collect();
+        let alphabet = corpus
+            .iter()
+            .flat_map(|(symbols, _)| symbols)
+            .collect::<HashSet<_>>()
+            .len();
+
+        let mut model = Self::default();
+        while alphabet + model.merges.len() < vocab_size {
+            let mut pairs: HashMap<(&str, &str), u64> = HashMap::new();
+            for (symbols, count) in &corpus {
+                for pair in symbols.windows(2) {
+                    *pairs.entry((&pair[0], &pair[1])).or_default() += count;
+                }
+            }
+            // Ties go to the alphabetically first pair so training is
+            // deterministic.
+            let Some(((left, right), count)) = pairs
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+            else {
+                break;
+            };
+            if count < MIN_FREQUENCY {
+                break;
+            }
+
+            let (left, right) = (left.to_string(), right.to_string());
+            for (symbols, _) in &mut corpus {
+                merge_pair(symbols, &left, &right);
+            }
+            model.push_merge(left, right);
+        }
+
+        model
+    }
+
+    /// Loads merges saved with [`BpeModel::save`]: one `left right` pair per
+    /// line in merge order, as in GPT-2's `merges.txt`.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read BPE model {}: {err}", path.display()))?;
+        let mut model = Self::default();
+
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(left), Some(right), None) => {
+                    model.push_merge(left.to_string(), right.to_string())
+                }
+                _ => {
+                    return Err(format!("{}:{}: malformed merge", path.display(), index + 1).into());
+                }
+            }
+        }
+
+        Ok(model)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut out = format!("{BPE_HEADER}\n");
+        for (left, right) in &self.merges {
+            out.push_str(&format!("{left} {right}\n"));
+        }
+        fs::write(path, out)
+            .map_err(|err| format!("cannot write BPE model {}: {err}", path.display()))?;
+        Ok(())
+    }
+
+    pub fn merges(&self) -> usize {
+        self.merges.len()
+    }
+
+    /// Replays the learned merges on `word`, always applying the earliest
+    /// learned merge that still occurs.
+    pub fn segment(&self, word: &str) -> Vec<String> {
+        let mut symbols: Vec<String> = clean_word(word).chars().map(String::from).collect();
+        loop {
+            let best = symbols
+                .windows(2)
+                .filter_map(|pair| self.ranks.get(&(pair[0].clone(), pair[1].clone())))
+                .min();
+            let Some(&rank) = best else {
+                break;
+            };
+            let (left, right) = &self.merges[rank];
+            merge_pair(&mut symbols, left, right);
+        }
+        symbols
+    }
+
+    fn push_merge(&mut self, left: String, right: String) {
+        self.ranks
+            .entry((left.clone(), right.clone()))
+            .or_insert(self.merges.len());
+        self.merges.push((left, right));
+    }
+}
+
+/// Joins every adjacent `left right` occurrence in `symbols`.
+fn merge_pair(symbols: &mut Vec<String>, left: &str, right: &str) {
+    let mut index = 0;
+    while index + 1 < symbols.len() {
+        if symbols[index] == left && symbols[index + 1] == right {
+            symbols[index].push_str(right);
+            symbols.remove(index + 1);
+        }
+        index += 1;
+    }
+}
+
+/// Unigram language model (as in SentencePiece): every piece has a
+/// probability, a word is split into its most probable piece sequence, and
+/// the vocabulary is found by EM from an oversized seed, pruning the pieces
+/// whose removal costs the corpus likelihood least.
+#[derive(Debug, Clone, Default)]
+pub struct UnigramModel {
+    /// Log probability of every piece.
+    pieces: BTreeMap<String, f64>,
+    max_len: usize,
+}
+
+impl UnigramModel {
+    pub fn train<'a>(words: impl IntoIterator<Item = &'a str>, vocab_size: usize) -> Self {
+        let counts = word_counts(words);
+
+        // Seed with every substring seen often enough, plus every letter so
+        // each word stays segmentable.
+        let mut seed: BTreeMap<String, f64> = BTreeMap::new();
+        for (word, count) in &counts {
+            for start in 0..word.len() {
+                for end in start + 1..=word.len().min(start + MAX_PIECE_LEN) {
+                    *seed.entry(word[start..end].to_string()).or_default() += *count as f64;
+                }
+            }
+        }
+        seed.retain(|piece, count| piece.len() == 1 || *count >= MIN_FREQUENCY as f64);
+        let mut model = Self::from_frequencies(seed);
+
+        loop {
+            for _ in 0..EM_STEPS {
+                model = Self::from_frequencies(model.expected_counts(&counts));
+            }
+            if model.pieces.len() <= vocab_size || !model.prune(&counts, vocab_size) {
+                break;
+            }
+        }
+
+        model
+    }
+
+    /// Loads a vocabulary saved with [`UnigramModel::save`]: one
+    /// `piece<TAB>log-probability` pair per line, as in SentencePiece's
+    /// `.vocab` files.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read unigram model {}: {err}", path.display()))?;
+        let mut model = Self::default();
+
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let malformed = || format!("{}:{}: malformed piece", path.display(), index + 1);
+            let (piece, log_prob) = line.split_once('\t').ok_or_else(malformed)?;
+            let log_prob: f64 = log_prob.trim().parse().map_err(|_| malformed())?;
+            if piece.is_empty() {
+                return Err(malformed().into());
+            }
+            model.max_len = model.max_len.max(piece.len());
+            model.pieces.insert(piece.to_string(), log_prob);
+        }
+
+        Ok(model)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut pieces: Vec<(&String, &f64)> = self.pieces.iter().collect();
+        pieces.sort_by(|a, b| b.1.total_cmp(a.1).then_with(|| a.0.cmp(b.0)));
+
+        let mut out = format!("{UNIGRAM_HEADER}\n");
+        for (piece, log_prob) in pieces {
+            out.push_str(&format!("{piece}\t{log_prob:.6}\n"));
+        }
+        fs::write(path, out)
+            .map_err(|err| format!("cannot write unigram model {}: {err}", path.display()))?;
+        Ok(())
+    }
+
+    pub fn pieces(&self) -> usize {
+        self.pieces.len()
+    }
+
+    /// The most probable piece sequence for `word`.
+    pub fn segment(&self, word: &str) -> Vec<String> {
+        let cleaned = clean_word(word);
+        self.best_path(&cleaned, None)
+            .map(|(_, spans)| {
+                spans
+                    .into_iter()
+                    .map(|(start, end)| cleaned[start..end].to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn from_frequencies(frequencies: BTreeMap<String, f64>) -> Self {
+        // Letters are always kept so every word stays segmentable.
+        let frequencies: BTreeMap<String, f64> = frequencies
+            .into_iter()
+            .filter(|(piece, count)| piece.len() == 1 || *count > 0.0)
+            .map(|(piece, count)| {
+                let count = if piece.len() == 1 {
+                    count.max(1.0)
+                } else {
+                    count
+                };
+                (piece, count)
+            })
+            .collect();
+        let total: f64 = frequencies.values().sum();
+        let max_len = frequencies.keys().map(String::len).max().unwrap_or(0);
+        let pieces = frequencies
+            .into_iter()
+            .map(|(piece, count)| (piece, (count / total).ln()))
+            .collect();
+        Self { pieces, max_len }
+    }
+
+    /// Log probability of `piece`; unseen single letters get a heavy penalty
+    /// so words with new characters can still be segmented.
+    fn log_prob(&self, piece: &str) -> Option<f64> {
+        match self.pieces.get(piece) {
+            Some(log_prob) => Some(*log_prob),
+            None if piece.len() == 1 => {
+                let rarest = self
+                    .pieces
+                    .values()
+                    .copied()
+                    .reduce(f64::min)
+                    .unwrap_or(0.0);
+                Some(rarest - UNKNOWN_PENALTY)
+            }
+            None => None,
+        }
+    }
+
+    /// Viterbi search for the most probable split of `word`, optionally
+    /// without using the piece `exclude` (used to price its removal).
+    fn best_path(&self, word: &str, exclude: Option<&str>) -> Option<(f64, Vec<(usize, usize)>)> {
+        let n = word.len();
+        if n == 0 {
+            return None;
+        }
+        let mut best: Vec<(f64, usize)> = vec![(f64::NEG_INFINITY, 0); n + 1];
+        best[0] = (0.0, 0);
+        for end in 1..=n {
+            for start in end.saturating_sub(self.max_len.max(1))..end {
+                let piece = &word[start..end];
+                if Some(piece) == exclude {
+                    continue;
+                }
+                let Some(log_prob) = self.log_prob(piece) else {
+                    continue;
+                };
+                let score = best[start].0 + log_prob;
+                if score > best[end].0 {
+                    best[end] = (score, start);
+                }
+            }
+        }
+        if best[n].0 == f64::NEG_INFINITY {
+            return None;
+        }
+
+        let mut spans = Vec::new();
+        let mut end = n;
+        while end > 0 {
+            let start = best[end].1;
+            spans.push((start, end));
+            end = start;
+        }
+        spans.reverse();
+        Some((best[n].0, spans))
+    }
+
+    /// E-step: expected occurrences of every piece under the current model,
+    /// summed over all segmentations of every word (forward-backward).
+    fn expected_counts(&self, counts: &BTreeMap<String, u64>) -> BTreeMap<String, f64> {
+        let mut expected: BTreeMap<String, f64> = BTreeMap::new();
+        for (word, count) in counts {
+            let n = word.len();
+            let spans = |start: usize| start + 1..=n.min(start + self.max_len);
+
+            let mut alpha = vec![f64::NEG_INFINITY; n + 1];
+            alpha[0] = 0.0;
+            for start in 0..n {
+                for end in spans(start) {
+                    if let Some(log_prob) = self.pieces.get(&word[start..end]) {
+                        alpha[end] = log_add(alpha[end], alpha[start] + log_prob);
+                    }
+                }
+            }
+            if alpha[n] == f64::NEG_INFINITY {
+                continue;
+            }
+
+            let mut beta = vec![f64::NEG_INFINITY; n + 1];
+            beta[n] = 0.0;
+            for start in (0..n).rev() {
+                for end in spans(start) {
+                    if let Some(log_prob) = self.pieces.get(&word[start..end]) {
+                        beta[start] = log_add(beta[start], log_prob + beta[end]);
+                    }
+                }
+            }
+
+            for start in 0..n {
+                for end in spans(start) {
+                    let piece = &word[start..end];
+                    if let Some(log_prob) = self.pieces.get(piece) {
+                        let posterior = (alpha[start] + log_prob + beta[end] - alpha[n]).exp();
+                        *expected.entry(piece.to_string()).or_default() +=
+                            *count as f64 * posterior;
+                    }
+                }
+            }
+        }
+        expected
+    }
+
+    /// Drops the multi-letter pieces whose removal loses the least
+    /// likelihood, keeping at least `SHRINK_FACTOR` of them per round and
+    /// never going below `vocab_size`. Returns false once only letters are
+    /// left to prune.
+    fn prune(&mut self, counts: &BTreeMap<String, u64>, vocab_size: usize) -> bool {
+        let expected = self.expected_counts(counts);
+        let letters = self.pieces.keys().filter(|piece| piece.len() == 1).count();
+        let mut losses: Vec<(f64, &String)> = self
+            .pieces
+            .iter()
+            .filter(|(piece, _)| piece.len() > 1)
+            .map(|(piece, log_prob)| {
+                let alternative = self
+                    .best_path(piece, Some(piece))
+                    .map_or(f64::NEG_INFINITY, |(score, _)| score);
+                let count = expected.get(piece).copied().unwrap_or(0.0);
+                (count * (log_prob - alternative), piece)
+            })
+            .collect();
+        if losses.is_empty() {
+            return false;
+        }
+        losses.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+
+        let keep = vocab_size
+            .saturating_sub(letters)
+            .max((losses.len() as f64 * SHRINK_FACTOR) as usize);
+        let kept: BTreeMap<String, f64> = self
+            .pieces
+            .iter()
+            .filter(|(piece, _)| piece.len() == 1)
+            .chain(losses.iter().take(keep).map(|(_, piece)| (*piece, &0.0)))
+            .map(|(piece, _)| {
+                let count = expected.get(piece).copied().unwrap_or(0.0);
+                (piece.clone(), count)
+            })
+            .collect();
+        *self = Self::from_frequencies(kept);
+        true
+    }
+}
+
+/// `ln(e^a + e^b)` without overflow.
+fn log_add(a: f64, b: f64) -> f64 {
+    if a == f64::NEG_INFINITY {
+        return b;
+    }
+    if b == f64::NEG_INFINITY {
+        return a;
+    }
+    let (high, low) = if a > b { (a, b) } else { (b, a) };
+    high + (low - high).exp().ln_1p()
+}
+
+/// Cleaned training words with how often each occurs.
+fn word_counts<'a>(words: impl IntoIterator<Item = &'a str>) -> BTreeMap<String, u64> {
+    let mut counts = BTreeMap::new();
+    for word in words {
+        let word = clean_word(word);
+        if !word.is_empty() {
+            *counts.entry(word).or_default() += 1;
+        }
+    }
+    counts
+}
@@ -0,0 +1,202 @@
+//! Syllable counting for the readability features: vowel-group heuristics
+//! with an exception list, overridden by CMUdict pronunciations when
+//! `--cmudict` is given.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::dictionary::LineIssue;
+
+/// Words the heuristics miscount.
+const EXCEPTIONS: &[(&str, usize)] = &[
+    ("area", 3),
+    ("being", 2),
+    ("business", 2),
+    ("create", 2),
+    ("created", 3),
+    ("every", 2),
+    ("idea", 3),
+    ("ideas", 3),
+    ("lion", 2),
+    ("naive", 2),
+    ("people", 2),
+    ("poem", 2),
+    ("poet", 2),
+    ("quiet", 2),
+    ("science", 2),
+    ("something", 2),
+    ("sometimes", 2),
+    ("whereas", 2),
+    ("whole", 1),
+    ("wednesday", 2),
+];
+
+/// Vowel pairs spoken as two syllables ("cre-ation", "vi-olin").
+const HIATUS: [&str; 6] = ["ia", "io", "iu", "eo", "ua", "uo"];
+/// Consonants after which `-ia`/`-io` is a single syllable ("nation",
+/// "special", "vision").
+const PALATAL: [char; 4] = ['t', 's', 'c', 'x'];
+/// Consonants after which `u` is a glide ("quality", "language").
+const GLIDING: [char; 2] = ['q', 'g'];
+
+#[derive(Debug, Clone, Default)]
+pub struct Syllabifier {
+    /// Known counts, which win over the heuristics.
+    known: HashMap<String, usize>,
+}
+
+impl Syllabifier {
+    pub fn builtin() -> Self {
+        Self {
+            known: EXCEPTIONS
+                .iter()
+                .map(|(word, count)| (word.to_string(), *count))
+                .collect(),
+        }
+    }
+
+    /// Merges a CMU Pronouncing Dictionary file (`WORD  P1 P2 ...` lines
+    /// whose vowel phonemes carry a stress digit). Only the first
+    /// pronunciation of a word is used; `;;;` comments are skipped and
+    /// lines without a vowel phoneme are returned as issues.
+    pub fn load_cmudict(&mut self, path: &Path) -> Result<Vec<LineIssue>, Box<dyn Error>> {
+        let file = File::open(path)
+            .map_err(|err| format!("cannot read CMUdict {}: {err}", path.display()))?;
+        let mut issues = Vec::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line =
+                line.map_err(|err| format!("cannot read CMUdict {}: {err}", path.display()))?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(";;;") {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let Some(word) = fields.next() else {
+                continue;
+            };
+            // Alternative pronunciations are spelt `WORD(2)`.
+            if word.ends_with(')') {
+                continue;
+            }
+            let count = fields
+                .filter(|phoneme| phoneme.ends_with(|c: char| c.is_ascii_digit()))
+                .count();
+            if count == 0 {
+                issues.push(LineIssue::new(index + 1, "no vowel phoneme"));
+                continue;
+            }
+            self.known.insert(word.to_lowercase(), count);
+        }
+        Ok(issues)
+    }
+
+    /// Syllables in `word`, at least one.
+    pub fn count(&self, word: &str) -> usize {
+        let word = word.to_lowercase();
+        if let Some(&count) = self.known.get(&word) {
+            return count;
+        }
+        heuristic(&word)
+    }
+}
+
+/// Counts vowel groups, then corrects for silent endings and hiatus.
+fn heuristic(word: &str) -> usize {
+    let letters: Vec<char> = word.chars().filter(char::is_ascii_alphabetic).collect();
+    let is_vowel = |index: usize| {
+        matches!(letters[index], 'a' | 'e' | 'i' | 'o' | 'u')
+            || (letters[index] == 'y' && index > 0)
+    };
+    let mut count = (0..letters.len())
+        .filter(|&index| is_vowel(index) && (index == 0 || !is_vowel(index - 1)))
+        .count() as isize;
+
+    let text: String = letters.iter().collect();
+    let length = letters.len();
+    // A final "e" is silent ("stage"), except in a consonant + "le" ending
+    // ("table") or where it is the only vowel ("the").
+    if text.ends_with('e')
+        && !text.ends_with("ee")
+        && !(text.ends_with("le") && length > 2 && !is_vowel(length - 3))
+    {
+        count -= 1;
+    }
+    // "-es" and "-ed" add no syllable unless the stem ends in a sibilant or
+    // in "t"/"d" ("hopes", "hoped" but "boxes", "wanted").
+    if length > 3 && (text.ends_with("es") || text.ends_with("ed")) && !is_vowel(length - 3) {
+        let before = &text[..length - 2];
+        let syllabic = if text.ends_with("es") {
+            before.ends_with(['s', 'x', 'z', 'c', 'g'])
+                || before.ends_with("ch")
+                || before.ends_with("sh")
+        } else {
+            before.ends_with(['t', 'd'])
+        };
+        if !syllabic {
+            count -= 1;
+        }
+    }
+    for pair in HIATUS {
+        for (position, _) in text.match_indices(pair) {
+            let before = &text[..position];
+            let single = match pair.as_bytes()[0] {
+                b'i' => before.ends_with(PALATAL),
+                b'u' => before.ends_with(GLIDING),
+                _ => false,
+            };
+            if !single {
+                count += 1;
+            }
+        }
+    }
+    count.max(1) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heuristics_correct_silent_endings_and_hiatus() {
+        let syllabifier = Syllabifier::builtin();
+        for (word, syllables) in [
+            ("stage", 1),
+            ("table", 2),
+            ("the", 1),
+            ("hopes", 1),
+            ("hoped", 1),
+            ("boxes", 2),
+            ("wanted", 2),
+            ("violin", 3),
+            ("nation", 2),
+            ("language", 2),
+            ("quality", 3),
+            ("People", 2),
+        ] {
+            assert_eq!(syllabifier.count(word), syllables, "{word}");
+        }
+    }
+
+    #[test]
+    fn cmudict_counts_stressed_vowels_and_reports_bad_lines() {
+        let path =
+            std::env::temp_dir().join(format!("erebus-{}-unit-cmudict.txt", std::process::id()));
+        std::fs::write(
+            &path,
+            ";;; comment\nBEYOND  B IH0 AA1 N D\nBEYOND(2)  B IY0 AA1 N D AH0\nHMM\n",
+        )
+        .expect("failed to write temp file");
+        let mut syllabifier = Syllabifier::builtin();
+        assert_eq!(syllabifier.count("beyond"), 1);
+        let issues = syllabifier.load_cmudict(&path).expect("file is readable");
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].to_string(), "line 4: no vowel phoneme");
+        // Only the first pronunciation counts.
+        assert_eq!(syllabifier.count("Beyond"), 2);
+    }
+}
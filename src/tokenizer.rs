@@ -0,0 +1,361 @@
+//! Definition tokenization shared by every feature extractor: definitions
+//! are split into lowercase ASCII words, which then pass through the
+//! normalizers chosen with `--normalize` in the order given, so that
+//! "steals" and "steal", or "colours" and "color", become one token.
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+use crate::cli::Options;
+use crate::orthography::Alternation;
+use crate::stemmer;
+
+/// Inflectional endings stripped when looking for a word's lemma.
+const INFLECTIONS: [&str; 6] = ["s", "es", "ed", "ing", "er", "est"];
+
+/// Lemmas no suffix rule can find.
+const IRREGULAR_LEMMAS: &[(&str, &str)] = &[
+    ("am", "be"),
+    ("are", "be"),
+    ("is", "be"),
+    ("was", "be"),
+    ("were", "be"),
+    ("been", "be"),
+    ("being", "be"),
+    ("has", "have"),
+    ("had", "have"),
+    ("does", "do"),
+    ("did", "do"),
+    ("done", "do"),
+    ("went", "go"),
+    ("gone", "go"),
+    ("made", "make"),
+    ("took", "take"),
+    ("taken", "take"),
+    ("gave", "give"),
+    ("given", "give"),
+    ("came", "come"),
+    ("saw", "see"),
+    ("seen", "see"),
+    ("knew", "know"),
+    ("known", "know"),
+    ("thought", "think"),
+    ("told", "tell"),
+    ("found", "find"),
+    ("got", "get"),
+    ("felt", "feel"),
+    ("left", "leave"),
+    ("kept", "keep"),
+    ("held", "hold"),
+    ("brought", "bring"),
+    ("began", "begin"),
+    ("begun", "begin"),
+    ("ran", "run"),
+    ("wrote", "write"),
+    ("written", "write"),
+    ("spoke", "speak"),
+    ("spoken", "speak"),
+    ("stole", "steal"),
+    ("stolen", "steal"),
+    ("broke", "break"),
+    ("broken", "break"),
+    ("chose", "choose"),
+    ("chosen", "choose"),
+    ("fell", "fall"),
+    ("fallen", "fall"),
+    ("grew", "grow"),
+    ("grown", "grow"),
+    ("drew", "draw"),
+    ("drawn", "draw"),
+    ("flew", "fly"),
+    ("flown", "fly"),
+    ("wore", "wear"),
+    ("worn", "wear"),
+    ("sold", "sell"),
+    ("bought", "buy"),
+    ("caught", "catch"),
+    ("taught", "teach"),
+    ("fought", "fight"),
+    ("sought", "seek"),
+    ("built", "build"),
+    ("sent", "send"),
+    ("spent", "spend"),
+    ("meant", "mean"),
+    ("stood", "stand"),
+    ("children", "child"),
+    ("men", "man"),
+    ("women", "woman"),
+    ("people", "person"),
+    ("feet", "foot"),
+    ("teeth", "tooth"),
+    ("mice", "mouse"),
+    ("geese", "goose"),
+    ("better", "good"),
+    ("best", "good"),
+    ("worse", "bad"),
+    ("worst", "bad"),
+];
+
+/// British spellings and their American equivalents.
+const AMERICAN_SPELLINGS: &[(&str, &str)] = &[
+    ("colour", "color"),
+    ("favour", "favor"),
+    ("honour", "honor"),
+    ("humour", "humor"),
+    ("labour", "labor"),
+    ("neighbour", "neighbor"),
+    ("behaviour", "behavior"),
+    ("flavour", "flavor"),
+    ("harbour", "harbor"),
+    ("rumour", "rumor"),
+    ("vapour", "vapor"),
+    ("vigour", "vigor"),
+    ("armour", "armor"),
+    ("odour", "odor"),
+    ("centre", "center"),
+    ("metre", "meter"),
+    ("theatre", "theater"),
+    ("fibre", "fiber"),
+    ("litre", "liter"),
+    ("calibre", "caliber"),
+    ("sombre", "somber"),
+    ("defence", "defense"),
+    ("offence", "offense"),
+    ("licence", "license"),
+    ("pretence", "pretense"),
+    ("analyse", "analyze"),
+    ("paralyse", "paralyze"),
+    ("organise", "organize"),
+    ("realise", "realize"),
+    ("recognise", "recognize"),
+    ("apologise", "apologize"),
+    ("criticise", "criticize"),
+    ("emphasise", "emphasize"),
+    ("catalogue", "catalog"),
+    ("dialogue", "dialog"),
+    ("grey", "gray"),
+    ("tyre", "tire"),
+    ("aluminium", "aluminum"),
+    ("programme", "program"),
+    ("jewellery", "jewelry"),
+    ("plough", "plow"),
+    ("sceptic", "skeptic"),
+    ("mould", "mold"),
+    ("moustache", "mustache"),
+    ("pyjamas", "pajamas"),
+    ("cheque", "check"),
+    ("ageing", "aging"),
+    ("travelled", "traveled"),
+    ("travelling", "traveling"),
+    ("traveller", "traveler"),
+];
+
+static ALTERNATIONS: LazyLock<Vec<Alternation>> = LazyLock::new(Alternation::inflectional);
+
+/// One step of the token pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Normalizer {
+    /// British spellings become American ("colours" → "colors").
+    Spelling,
+    /// Inflected forms become their lemma, from the irregular table and
+    /// `--lemmas`, else by stripping an inflection when that leaves a word
+    /// seen in some definition ("steals" → "steal", "went" → "go").
+    Lemma,
+    /// The Porter2 stem ("stages" → "stage", "consolation" → "consol").
+    Stem,
+}
+
+impl FromStr for Normalizer {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "spelling" => Ok(Normalizer::Spelling),
+            "lemma" => Ok(Normalizer::Lemma),
+            "stem" => Ok(Normalizer::Stem),
+            other => Err(format!(
+                "unknown normalizer '{other}' (expected 'spelling', 'lemma' or 'stem')"
+            )),
+        }
+    }
+}
+
+/// A definition as the feature extractors see it: its normalized tokens,
+/// plus the raw text for extractors that need surface spellings or
+/// sentence boundaries.
+#[derive(Debug, Clone)]
+pub struct Document<'a> {
+    pub text: &'a str,
+    /// The part-of-speech label the dictionary gives the sense, if any.
+    pub pos: Option<&'a str>,
+    pub tokens: Vec<String>,
+}
+
+impl Document<'_> {
+    /// The words of the text before normalization.
+    pub fn words(&self) -> Vec<String> {
+        words(self.text).collect()
+    }
+}
+
+/// Splits definitions into words and normalizes them.
+#[derive(Debug, Clone, Default)]
+pub struct Tokenizer {
+    normalizers: Vec<Normalizer>,
+    lemmas: HashMap<String, String>,
+    spellings: HashMap<String, String>,
+    /// Every word of the fitted definitions, which the lemma rules must land
+    /// on.
+    known: HashSet<String>,
+}
+
+impl Tokenizer {
+    /// The `--normalize` pipeline, with the `--lemmas` table merged over the
+    /// built-in irregular forms.
+    pub fn new(options: &Options) -> Result<Self, Box<dyn Error>> {
+        let mut lemmas: HashMap<String, String> = IRREGULAR_LEMMAS
+            .iter()
+            .map(|(form, lemma)| (form.to_string(), lemma.to_string()))
+            .collect();
+        if let Some(path) = &options.lemmas {
+            lemmas.extend(load_lemmas(path)?);
+        }
+        Ok(Self {
+            normalizers: options.normalizers.clone(),
+            lemmas,
+            spellings: AMERICAN_SPELLINGS
+                .iter()
+                .map(|(british, american)| (british.to_string(), american.to_string()))
+                .collect(),
+            known: HashSet::new(),
+        })
+    }
+
+    /// Records the words of every definition for the lemma rules.
+    pub fn fit(&mut self, definitions: &[&str]) {
+        self.known = definitions
+            .iter()
+            .flat_map(|definition| words(definition))
+            .flat_map(|word| {
+                let american = self.american(&word);
+                [word, american]
+            })
+            .collect();
+    }
+
+    pub fn tokenize<'a>(&self, definition: &'a str) -> Document<'a> {
+        Document {
+            text: definition,
+            pos: None,
+            tokens: words(definition)
+                .map(|word| self.normalize(&word))
+                .collect(),
+        }
+    }
+
+    /// `word` after every normalizer, in order.
+    pub fn normalize(&self, word: &str) -> String {
+        let mut word = word.to_lowercase();
+        for normalizer in &self.normalizers {
+            word = match normalizer {
+                Normalizer::Spelling => self.american(&word),
+                Normalizer::Lemma => self.lemma(&word),
+                Normalizer::Stem => stemmer::stem(&word),
+            };
+        }
+        word
+    }
+
+    /// The American spelling of `word`, also through a regular inflection
+    /// ("centres" → "centers").
+    fn american(&self, word: &str) -> String {
+        if let Some(american) = self.spellings.get(word) {
+            return american.clone();
+        }
+        INFLECTIONS
+            .iter()
+            .find_map(|inflection| {
+                let stem = word.strip_suffix(inflection)?;
+                let american = self.spellings.get(stem)?;
+                Some(format!("{american}{inflection}"))
+            })
+            .unwrap_or_else(|| word.to_string())
+    }
+
+    fn lemma(&self, word: &str) -> String {
+        self.lemma_among(word, |candidate| self.known.contains(candidate))
+    }
+
+    /// Whether the pipeline reduces words to their lemmas.
+    pub fn lemmatizes(&self) -> bool {
+        self.normalizers.contains(&Normalizer::Lemma)
+    }
+
+    /// The lemma of `word` from the irregular table and `--lemmas`, else
+    /// its first lemma candidate that `is_known` accepts, else the word
+    /// itself.
+    pub fn lemma_among(&self, word: &str, is_known: impl Fn(&str) -> bool) -> String {
+        if let Some(lemma) = self.lemmas.get(word) {
+            return lemma.clone();
+        }
+        lemma_candidates(word)
+            .into_iter()
+            .find(|candidate| is_known(candidate))
+            .unwrap_or_else(|| word.to_string())
+    }
+}
+
+/// Possible lemmas of an inflected `word`, most plausible first: for each
+/// inflection it ends with, the stem with the spelling change the suffix
+/// caused undone ("hoped" → "hope", "stopped" → "stop", "studies" →
+/// "study"), then the bare stem ("glowing" → "glow").
+fn lemma_candidates(word: &str) -> Vec<String> {
+    let mut candidates = Vec::new();
+    for inflection in INFLECTIONS {
+        let Some(stem) = word.strip_suffix(inflection) else {
+            continue;
+        };
+        if stem.len() < 3 {
+            continue;
+        }
+        candidates.extend(
+            ALTERNATIONS
+                .iter()
+                .filter_map(|rule| rule.restore(stem, inflection)),
+        );
+        candidates.push(stem.to_string());
+    }
+    candidates
+}
+
+/// Lowercase ASCII words of a definition.
+fn words(definition: &str) -> impl Iterator<Item = String> + '_ {
+    definition
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_ascii_lowercase())
+}
+
+/// A `form<TAB>lemma` table; blank lines and `#` comments are skipped.
+fn load_lemmas(path: &Path) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| format!("cannot read lemmas {}: {err}", path.display()))?;
+    let mut lemmas = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((form, lemma)) = line.split_once('\t') else {
+            return Err(
+                format!("{}:{}: expected form<TAB>lemma", path.display(), index + 1).into(),
+            );
+        };
+        lemmas.push((form.trim().to_lowercase(), lemma.trim().to_lowercase()));
+    }
+    Ok(lemmas)
+}
@@ -0,0 +1,501 @@
+//! Minimal TOML reader covering the subset used by configuration files:
+//! bare or quoted keys, strings, integers, floats, booleans, (multi-line)
+//! arrays, inline tables, `[table]` headers and `[[array-of-tables]]`
+//! headers. Dotted keys and multi-line strings are not supported.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TomlValue {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Array(Vec<TomlValue>),
+    Table(TomlTable),
+}
+
+impl TomlValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            TomlValue::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            TomlValue::String(_) => "string",
+            TomlValue::Integer(_) => "integer",
+            TomlValue::Float(_) => "float",
+            TomlValue::Bool(_) => "boolean",
+            TomlValue::Array(_) => "array",
+            TomlValue::Table(_) => "table",
+        }
+    }
+}
+
+/// Key/value pairs in document order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TomlTable {
+    pub entries: Vec<(String, TomlValue)>,
+}
+
+impl TomlTable {
+    pub fn get(&self, key: &str) -> Option<&TomlValue> {
+        self.entries
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut TomlValue> {
+        self.entries
+            .iter_mut()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TomlError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TomlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for TomlError {}
+
+/// Parses a whole document into its root table.
+pub fn parse(text: &str) -> Result<TomlTable, TomlError> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    let mut root = TomlTable::default();
+    // Path of the table currently receiving keys: (header name, is array table).
+    let mut current: Option<(String, bool)> = None;
+
+    loop {
+        parser.skip_blank_lines();
+        let Some(c) = parser.peek() else {
+            break;
+        };
+
+        // Errors found once the line has been consumed still point at it.
+        let line = parser.line;
+        if c == '[' {
+            parser.pos += 1;
+            let is_array = parser.peek() == Some('[');
+            if is_array {
+                parser.pos += 1;
+            }
+            parser.skip_spaces();
+            let name = parser.key()?;
+            parser.skip_spaces();
+            parser.expect(']')?;
+            if is_array {
+                parser.expect(']')?;
+            }
+            parser.end_of_line()?;
+
+            match (root.get_mut(&name), is_array) {
+                (None, false) => root
+                    .entries
+                    .push((name.clone(), TomlValue::Table(TomlTable::default()))),
+                (None, true) => root.entries.push((
+                    name.clone(),
+                    TomlValue::Array(vec![TomlValue::Table(TomlTable::default())]),
+                )),
+                (Some(TomlValue::Array(tables)), true) => {
+                    tables.push(TomlValue::Table(TomlTable::default()))
+                }
+                _ => {
+                    return Err(TomlError {
+                        line,
+                        message: format!("table '{name}' defined twice"),
+                    });
+                }
+            }
+            current = Some((name, is_array));
+            continue;
+        }
+
+        let key = parser.key()?;
+        parser.skip_spaces();
+        parser.expect('=')?;
+        parser.skip_spaces();
+        let value = parser.value()?;
+        parser.end_of_line()?;
+
+        let table = match &current {
+            None => &mut root,
+            Some((name, is_array)) => match (root.get_mut(name), is_array) {
+                (Some(TomlValue::Table(table)), false) => table,
+                (Some(TomlValue::Array(tables)), true) => match tables.last_mut() {
+                    Some(TomlValue::Table(table)) => table,
+                    _ => unreachable!("array tables only hold tables"),
+                },
+                _ => unreachable!("current table always exists"),
+            },
+        };
+        if table.get(&key).is_some() {
+            return Err(TomlError {
+                line,
+                message: format!("duplicate key '{key}'"),
+            });
+        }
+        table.entries.push((key, value));
+    }
+
+    Ok(root)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn error(&self, message: impl Into<String>) -> TomlError {
+        TomlError {
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), TomlError> {
+        if self.peek() == Some(expected) {
+            self.bump();
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{expected}'")))
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), Some('\n') | None) {
+                self.bump();
+            }
+        }
+    }
+
+    /// Skips whitespace, newlines and comments.
+    fn skip_blank_lines(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n' | '\r') => {
+                    self.bump();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), TomlError> {
+        self.skip_spaces();
+        self.skip_comment();
+        if self.peek() == Some('\r') {
+            self.bump();
+        }
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("unexpected '{c}' after value"))),
+        }
+    }
+
+    fn key(&mut self) -> Result<String, TomlError> {
+        match self.peek() {
+            Some('"') => self.basic_string(),
+            Some('\'') => self.literal_string(),
+            _ => {
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                {
+                    self.bump();
+                }
+                if start == self.pos {
+                    return Err(self.error("expected a key"));
+                }
+                Ok(self.chars[start..self.pos].iter().collect())
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<TomlValue, TomlError> {
+        match self.peek() {
+            Some('"') => self.basic_string().map(TomlValue::String),
+            Some('\'') => self.literal_string().map(TomlValue::String),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some('t' | 'f') => {
+                let word = self.word();
+                match word.as_str() {
+                    "true" => Ok(TomlValue::Bool(true)),
+                    "false" => Ok(TomlValue::Bool(false)),
+                    _ => Err(self.error(format!("invalid value '{word}'"))),
+                }
+            }
+            Some(c) if c == '+' || c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) => Err(self.error(format!("unexpected '{c}' where a value was expected"))),
+            None => Err(self.error("missing value")),
+        }
+    }
+
+    fn word(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-' | '.'))
+        {
+            self.bump();
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn number(&mut self) -> Result<TomlValue, TomlError> {
+        let raw = self.word().replace('_', "");
+        if let Ok(integer) = raw.parse::<i64>() {
+            return Ok(TomlValue::Integer(integer));
+        }
+        raw.parse::<f64>()
+            .map(TomlValue::Float)
+            .map_err(|_| self.error(format!("invalid number '{raw}'")))
+    }
+
+    fn basic_string(&mut self) -> Result<String, TomlError> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            if matches!(self.peek(), Some('\n') | None) {
+                return Err(self.error("unterminated string"));
+            }
+            match self.bump() {
+                Some('"') => return Ok(out),
+                Some('\\') => match self.bump() {
+                    Some('"') => out.push('"'),
+                    Some('\\') => out.push('\\'),
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some('r') => out.push('\r'),
+                    Some('u') => {
+                        let digits: String = (0..4).filter_map(|_| self.bump()).collect();
+                        let code = u32::from_str_radix(&digits, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| self.error("invalid \\u escape"))?;
+                        out.push(code);
+                    }
+                    _ => return Err(self.error("invalid escape sequence")),
+                },
+                Some(c) => out.push(c),
+                None => unreachable!("checked above"),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, TomlError> {
+        self.expect('\'')?;
+        let mut out = String::new();
+        loop {
+            if matches!(self.peek(), Some('\n') | None) {
+                return Err(self.error("unterminated string"));
+            }
+            match self.bump() {
+                Some('\'') => return Ok(out),
+                Some(c) => out.push(c),
+                None => unreachable!("checked above"),
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<TomlValue, TomlError> {
+        self.expect('{')?;
+        let mut table = TomlTable::default();
+        self.skip_spaces();
+        if self.peek() == Some('}') {
+            self.bump();
+            return Ok(TomlValue::Table(table));
+        }
+        loop {
+            self.skip_spaces();
+            let key = self.key()?;
+            self.skip_spaces();
+            self.expect('=')?;
+            self.skip_spaces();
+            let value = self.value()?;
+            if table.get(&key).is_some() {
+                return Err(self.error(format!("duplicate key '{key}'")));
+            }
+            table.entries.push((key, value));
+            self.skip_spaces();
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some('}') => {
+                    self.bump();
+                    return Ok(TomlValue::Table(table));
+                }
+                _ => return Err(self.error("expected ',' or '}' in inline table")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<TomlValue, TomlError> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank_lines();
+            if self.peek() == Some(']') {
+                self.bump();
+                return Ok(TomlValue::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_blank_lines();
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some(']') => {
+                    self.bump();
+                    return Ok(TomlValue::Array(items));
+                }
+                _ => return Err(self.error("expected ',' or ']' in array")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_scalars_arrays_and_tables() {
+        let document = parse(
+            "# profile\n\
+             name = \"en\"\n\
+             extends = 'builtin'\n\
+             version = 1_000\n\
+             weight = -0.5\n\
+             strict = false\n\
+             linkers = [\n  \"s\", # genitive\n  \"o\",\n]\n\
+             \n\
+             [prefixes]\n\
+             un = { gloss = \"not\", priority = 2 }\n\
+             \n\
+             [[alternation]]\n\
+             name = \"e-deletion\"\n\
+             [[alternation]]\n\
+             name = \"y-to-i\"\n",
+        )
+        .unwrap();
+        assert_eq!(document.get("name").and_then(TomlValue::as_str), Some("en"));
+        assert_eq!(
+            document.get("extends").and_then(TomlValue::as_str),
+            Some("builtin")
+        );
+        assert_eq!(document.get("version"), Some(&TomlValue::Integer(1000)));
+        assert_eq!(document.get("weight"), Some(&TomlValue::Float(-0.5)));
+        assert_eq!(document.get("strict"), Some(&TomlValue::Bool(false)));
+        assert_eq!(
+            document.get("linkers"),
+            Some(&TomlValue::Array(vec![
+                TomlValue::String("s".to_string()),
+                TomlValue::String("o".to_string()),
+            ]))
+        );
+
+        let Some(TomlValue::Table(prefixes)) = document.get("prefixes") else {
+            panic!("prefixes is a table");
+        };
+        let Some(TomlValue::Table(un)) = prefixes.get("un") else {
+            panic!("un is an inline table");
+        };
+        assert_eq!(un.get("gloss").and_then(TomlValue::as_str), Some("not"));
+        assert_eq!(un.get("priority"), Some(&TomlValue::Integer(2)));
+
+        let Some(TomlValue::Array(alternations)) = document.get("alternation") else {
+            panic!("alternation is an array of tables");
+        };
+        assert_eq!(alternations.len(), 2);
+        let TomlValue::Table(second) = &alternations[1] else {
+            panic!("array tables hold tables");
+        };
+        assert_eq!(
+            second.get("name").and_then(TomlValue::as_str),
+            Some("y-to-i")
+        );
+    }
+
+    #[test]
+    fn decodes_string_escapes() {
+        let document = parse(r#"text = "a\"b\\c\td\u00e9""#).unwrap();
+        assert_eq!(
+            document.get("text").and_then(TomlValue::as_str),
+            Some("a\"b\\c\tdé")
+        );
+        let document = parse(r"path = 'C:\raw'").unwrap();
+        assert_eq!(
+            document.get("path").and_then(TomlValue::as_str),
+            Some(r"C:\raw")
+        );
+    }
+
+    #[test]
+    fn reports_errors_with_line_numbers() {
+        let error = |text: &str| parse(text).unwrap_err();
+        assert_eq!(
+            error("a = 1\na = 2\n"),
+            TomlError {
+                line: 2,
+                message: "duplicate key 'a'".to_string()
+            }
+        );
+        assert_eq!(error("[t]\n[t]\n").message, "table 't' defined twice");
+        assert_eq!(error("\n\na = \"open\n").line, 3);
+        assert_eq!(error("a = 1 2\n").message, "unexpected '2' after value");
+        assert_eq!(
+            error("a = yes\n").message,
+            "unexpected 'y' where a value was expected"
+        );
+        assert_eq!(error("a = [1 2]\n").message, "expected ',' or ']' in array");
+        assert_eq!(error("a.b = 1\n").message, "expected '='");
+    }
+}
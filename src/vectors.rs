@@ -0,0 +1,110 @@
+//! Pretrained word vectors in the plain-text formats shared by word2vec,
+//! GloVe and fastText: one `word v1 v2 ... vN` line per word, with an
+//! optional `count dimensions` header line (word2vec, fastText `.vec`).
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::dictionary::LineIssue;
+
+#[derive(Debug, Default)]
+pub struct WordVectors {
+    dimensions: usize,
+    index: HashMap<String, usize>,
+    vectors: Vec<Vec<f32>>,
+    /// Lines read from the file, vectors kept or not.
+    pub lines: usize,
+    pub issues: Vec<LineIssue>,
+}
+
+impl WordVectors {
+    /// Streams `path`, keeping only the words `key` files under some key so
+    /// that multi-gigabyte files cost memory only for the words actually
+    /// needed. Words are lowercased before `key` sees them; when several
+    /// words collapse to one key the first in the file wins, which for
+    /// frequency-sorted files is the most common one. Kept lines with the
+    /// wrong number of values or unparsable numbers are reported and skipped.
+    pub fn load(path: &Path, key: impl Fn(&str) -> Option<String>) -> Result<Self, Box<dyn Error>> {
+        let file = File::open(path)
+            .map_err(|err| format!("cannot read word vectors {}: {err}", path.display()))?;
+        let mut vectors = Self::default();
+
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line_number = index + 1;
+            let line =
+                line.map_err(|err| format!("cannot read word vectors {}: {err}", path.display()))?;
+            let mut fields = line.split_whitespace();
+            let Some(word) = fields.next() else {
+                continue;
+            };
+            vectors.lines += 1;
+
+            if line_number == 1
+                && let [count, dimensions] = line.split_whitespace().collect::<Vec<_>>()[..]
+                && count.parse::<usize>().is_ok()
+                && let Ok(dimensions) = dimensions.parse::<usize>()
+            {
+                vectors.dimensions = dimensions;
+                vectors.lines -= 1;
+                continue;
+            }
+
+            if vectors.dimensions == 0 {
+                // The first vector fixes the width, whether or not its word
+                // is needed, so a file sharing no word with the definitions
+                // still yields zero vectors of the right size.
+                vectors.dimensions = fields.clone().count();
+            }
+            let Some(word) = key(&word.to_lowercase()) else {
+                continue;
+            };
+            if vectors.index.contains_key(&word) {
+                continue;
+            }
+            let values: Result<Vec<f32>, _> = fields.map(str::parse::<f32>).collect();
+            let Ok(values) = values else {
+                vectors
+                    .issues
+                    .push(LineIssue::new(line_number, "value is not a number"));
+                continue;
+            };
+            if values.is_empty() {
+                vectors
+                    .issues
+                    .push(LineIssue::new(line_number, "no values after the word"));
+                continue;
+            }
+            if values.len() != vectors.dimensions {
+                vectors.issues.push(LineIssue::new(
+                    line_number,
+                    format!(
+                        "expected {} values, found {}",
+                        vectors.dimensions,
+                        values.len()
+                    ),
+                ));
+                continue;
+            }
+            vectors.index.insert(word, vectors.vectors.len());
+            vectors.vectors.push(values);
+        }
+
+        Ok(vectors)
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// Number of vectors kept.
+    pub fn len(&self) -> usize {
+        self.vectors.len()
+    }
+
+    pub fn get(&self, word: &str) -> Option<&[f32]> {
+        self.index.get(word).map(|&id| self.vectors[id].as_slice())
+    }
+}
@@ -0,0 +1,308 @@
+//! Globally optimal segmentation: every split of the word into
+//! `prefix* root+ suffix*` is scored and the cheapest one wins, instead of
+//! peeling affixes greedily and chopping leftovers into fixed-width chunks.
+
+use std::collections::HashSet;
+
+use crate::morphology::MorphologyProfile;
+use crate::orthography;
+use crate::{Morpheme, MorphemeKind, clean_word};
+
+/// Costs (negative log-scores) used to rank segmentations; lower is better.
+#[derive(Debug, Clone)]
+pub struct ViterbiCosts {
+    /// Paid once per morpheme, so fewer and longer pieces are preferred.
+    pub morpheme: f32,
+    /// Flat extra cost of a root span the profile does not know.
+    pub unknown: f32,
+    /// Extra cost per character of an unknown root span.
+    pub unknown_char: f32,
+    /// Mean of the Gaussian length prior applied to known morphemes.
+    pub length_mean: f32,
+    /// Standard deviation of that length prior.
+    pub length_deviation: f32,
+    /// Bonus per point of profile priority.
+    pub priority_weight: f32,
+    /// Extra cost of a known root reached only through a spelling
+    /// alternation.
+    pub alternation: f32,
+}
+
+impl Default for ViterbiCosts {
+    fn default() -> Self {
+        Self {
+            morpheme: 0.5,
+            unknown: 2.0,
+            unknown_char: 1.5,
+            length_mean: 5.0,
+            length_deviation: 3.0,
+            priority_weight: 0.5,
+            alternation: 0.5,
+        }
+    }
+}
+
+impl ViterbiCosts {
+    fn known(&self, len: usize, priority: i64) -> f32 {
+        let z = (len as f32 - self.length_mean) / self.length_deviation;
+        self.morpheme + 0.5 * z * z - self.priority_weight * priority as f32
+    }
+
+    fn unknown(&self, len: usize) -> f32 {
+        self.morpheme + self.unknown + self.unknown_char * len as f32
+    }
+}
+
+/// Where a segmentation path stands after its last morpheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Start,
+    Prefix,
+    Root,
+    Suffix,
+}
+
+impl State {
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn after(kind: MorphemeKind) -> Self {
+        match kind {
+            MorphemeKind::Prefix => State::Prefix,
+            MorphemeKind::Root
+            | MorphemeKind::Subword
+            | MorphemeKind::Linker
+            | MorphemeKind::Stem => State::Root,
+            MorphemeKind::Suffix => State::Suffix,
+        }
+    }
+
+    /// Morphemes must appear as `prefix* root+ suffix*`.
+    fn allows(self, kind: MorphemeKind) -> bool {
+        matches!(
+            (self, kind),
+            (State::Start | State::Prefix, MorphemeKind::Prefix)
+                | (
+                    State::Start | State::Prefix | State::Root,
+                    MorphemeKind::Root
+                )
+                | (State::Root | State::Suffix, MorphemeKind::Suffix)
+        )
+    }
+}
+
+const STATES: usize = 4;
+
+/// A candidate morpheme spanning `start..end` of the cleaned word.
+#[derive(Debug, Clone)]
+struct Edge {
+    start: usize,
+    end: usize,
+    kind: MorphemeKind,
+    cost: f32,
+    /// Canonical form when the span is a spelling alternant of a known root.
+    underlying: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Back {
+    cost: f32,
+    edge: usize,
+    from: State,
+    /// Rank of the extended path within its `from` slot.
+    from_rank: usize,
+}
+
+/// Returns up to `n` segmentations of `word` with their total costs,
+/// cheapest first. Analyses that split the word at the same places and
+/// differ only in how they label the pieces (`prefix(hyper)` against
+/// `root(hyper)`) count once, at their cheapest labelling.
+pub fn nbest(
+    word: &str,
+    profile: &MorphologyProfile,
+    costs: &ViterbiCosts,
+    n: usize,
+) -> Vec<(Vec<Morpheme>, f32)> {
+    let cleaned = clean_word(word);
+    if cleaned.is_empty() || n == 0 {
+        return Vec::new();
+    }
+
+    let edges = lattice_edges(&cleaned, profile, costs);
+    // Relabelled duplicates use up paths, so widen the search until it
+    // yields `n` distinct splits or the lattice runs out of paths.
+    let mut width = n;
+    loop {
+        let paths = labelled_nbest(&cleaned, &edges, width);
+        let exhausted = paths.len() < width;
+        let mut splits = HashSet::new();
+        let distinct: Vec<_> = paths
+            .into_iter()
+            .filter(|(morphemes, _)| {
+                splits.insert(
+                    morphemes
+                        .iter()
+                        .map(|morpheme| morpheme.surface.len())
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .take(n)
+            .collect();
+        if distinct.len() == n || exhausted {
+            return distinct;
+        }
+        width *= 2;
+    }
+}
+
+/// The `n` cheapest labelled paths through the lattice. Every lattice slot
+/// keeps its `n` best partial paths, so the result is exact rather than a
+/// beam approximation.
+fn labelled_nbest(cleaned: &str, edges: &[Edge], n: usize) -> Vec<(Vec<Morpheme>, f32)> {
+    let len = cleaned.len();
+    let mut best: Vec<[Vec<Back>; STATES]> = vec![Default::default(); len + 1];
+    best[0][State::Start.index()].push(Back {
+        cost: 0.0,
+        edge: usize::MAX,
+        from: State::Start,
+        from_rank: 0,
+    });
+
+    // Edges are sorted by start position, so every slot at `start` is final
+    // before its outgoing edges are relaxed.
+    for (index, edge) in edges.iter().enumerate() {
+        let to = State::after(edge.kind);
+        for from in [State::Start, State::Prefix, State::Root, State::Suffix] {
+            if !from.allows(edge.kind) {
+                continue;
+            }
+            for rank in 0..best[edge.start][from.index()].len() {
+                let cost = best[edge.start][from.index()][rank].cost + edge.cost;
+                let slot = &mut best[edge.end][to.index()];
+                // Ties keep the earlier path first so results are stable.
+                let position = slot.partition_point(|back| back.cost <= cost);
+                if position < n {
+                    slot.insert(
+                        position,
+                        Back {
+                            cost,
+                            edge: index,
+                            from,
+                            from_rank: rank,
+                        },
+                    );
+                    slot.truncate(n);
+                }
+            }
+        }
+    }
+
+    let mut finals: Vec<(State, usize, f32)> = [State::Root, State::Suffix]
+        .into_iter()
+        .flat_map(|state| {
+            best[len][state.index()]
+                .iter()
+                .enumerate()
+                .map(move |(rank, back)| (state, rank, back.cost))
+        })
+        .collect();
+    finals.sort_by(|a, b| a.2.total_cmp(&b.2));
+    finals.truncate(n);
+
+    finals
+        .into_iter()
+        .map(|(mut state, mut rank, cost)| {
+            let mut morphemes = Vec::new();
+            let mut position = len;
+            while state != State::Start {
+                let back = best[position][state.index()][rank];
+                let edge = &edges[back.edge];
+                let surface = &cleaned[edge.start..edge.end];
+                morphemes.push(match &edge.underlying {
+                    Some(underlying) => Morpheme::alternant(edge.kind, surface, underlying.clone()),
+                    None => Morpheme::new(edge.kind, surface),
+                });
+                position = edge.start;
+                state = back.from;
+                rank = back.from_rank;
+            }
+            morphemes.reverse();
+            (morphemes, cost)
+        })
+        .collect()
+}
+
+/// Every known affix/root occurrence, an edge for each span that is a
+/// spelling alternant of a known root, and an unknown-root edge for each span.
+fn lattice_edges(word: &str, profile: &MorphologyProfile, costs: &ViterbiCosts) -> Vec<Edge> {
+    let n = word.len();
+    let mut edges = Vec::new();
+
+    for start in 0..n {
+        let mut known_root_ends = Vec::new();
+        for kind in [MorphemeKind::Prefix, MorphemeKind::Root] {
+            for spec in profile.candidates(kind, &word[start..]) {
+                let end = start + spec.text.len();
+                if kind == MorphemeKind::Root {
+                    known_root_ends.push(end);
+                }
+                edges.push(Edge {
+                    start,
+                    end,
+                    kind,
+                    cost: costs.known(spec.text.len(), spec.priority),
+                    underlying: None,
+                });
+            }
+        }
+        for end in start + 1..=n {
+            if !known_root_ends.contains(&end) {
+                edges.push(Edge {
+                    start,
+                    end,
+                    kind: MorphemeKind::Root,
+                    cost: costs.unknown(end - start),
+                    underlying: None,
+                });
+            }
+            // The letter after the span decides whether a spelling rule
+            // could have produced it from a known root.
+            if end < n
+                && !known_root_ends.contains(&end)
+                && let Some(underlying) = orthography::restore(
+                    &word[start..end],
+                    &word[end..],
+                    profile.alternations(),
+                    |form| profile.lookup(MorphemeKind::Root, form).is_some(),
+                )
+            {
+                let priority = profile
+                    .lookup(MorphemeKind::Root, &underlying)
+                    .map_or(0, |spec| spec.priority);
+                edges.push(Edge {
+                    start,
+                    end,
+                    kind: MorphemeKind::Root,
+                    cost: costs.known(end - start, priority) + costs.alternation,
+                    underlying: Some(underlying),
+                });
+            }
+        }
+    }
+
+    for end in 1..=n {
+        for spec in profile.candidates(MorphemeKind::Suffix, &word[..end]) {
+            edges.push(Edge {
+                start: end - spec.text.len(),
+                end,
+                kind: MorphemeKind::Suffix,
+                cost: costs.known(spec.text.len(), spec.priority),
+                underlying: None,
+            });
+        }
+    }
+
+    edges.sort_by_key(|edge| edge.start);
+    edges
+}
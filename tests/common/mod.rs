@@ -0,0 +1,103 @@
+//! Helpers shared by the integration tests; each test crate uses only some
+//! of them.
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Runs the binary from the crate root.
+pub fn erebus(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_erebus"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("failed to run erebus")
+}
+
+/// Runs the binary, checks that it succeeded and returns stdout and stderr.
+pub fn run_with_stderr(args: &[&str]) -> (String, String) {
+    let output = erebus(args);
+    assert!(
+        output.status.success(),
+        "erebus failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    (
+        String::from_utf8(output.stdout).expect("stdout is UTF-8"),
+        String::from_utf8(output.stderr).expect("stderr is UTF-8"),
+    )
+}
+
+/// Runs the binary, checks that it succeeded and returns stdout.
+pub fn run(args: &[&str]) -> String {
+    run_with_stderr(args).0
+}
+
+/// Runs the binary and returns each processed word mapped to its breakdown.
+pub fn segmentations(args: &[&str]) -> BTreeMap<String, String> {
+    run(args)
+        .lines()
+        .filter_map(|line| line.strip_prefix("- "))
+        .filter_map(|line| line.split_once(": "))
+        .map(|(word, breakdown)| (word.to_string(), breakdown.to_string()))
+        .collect()
+}
+
+pub fn temp_file(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("erebus-{}-{name}", std::process::id()));
+    fs::write(&path, contents).expect("failed to write temp file");
+    path
+}
+
+/// A word list of regular stems and suffixes for the learned segmenters.
+pub fn affixed_corpus(name: &str) -> PathBuf {
+    let stems = [
+        "walk", "play", "jump", "talk", "help", "work", "paint", "load",
+    ];
+    let suffixes = ["", "s", "ed", "ing", "er", "ful", "less", "able"];
+    let corpus: String = stems
+        .iter()
+        .flat_map(|stem| {
+            suffixes
+                .iter()
+                .map(move |suffix| format!("{stem}{suffix}\n"))
+        })
+        .collect();
+    temp_file(name, &corpus)
+}
+
+pub fn assert_segmentations(actual: &BTreeMap<String, String>, expected: &[(&str, &str)]) {
+    for (word, breakdown) in expected {
+        assert_eq!(
+            actual.get(*word).map(String::as_str),
+            Some(*breakdown),
+            "segmentation of '{word}'"
+        );
+    }
+}
+
+/// Runs the binary over only the definitions in `dictionary`, with the
+/// argument groups in `args` appended in order, and returns stdout and
+/// stderr.
+pub fn run_on(dictionary: &Path, args: &[&[&str]]) -> (String, String) {
+    let dictionary = dictionary.to_str().expect("temp path is UTF-8");
+    let mut all = vec!["--replace-dictionary", "--dictionary", dictionary];
+    all.extend(args.iter().flat_map(|group| group.iter().copied()));
+    run_with_stderr(&all)
+}
+
+/// The values of `morpheme`'s row in a printed embedding matrix, without its
+/// count.
+pub fn row(stdout: &str, morpheme: &str) -> String {
+    stdout
+        .lines()
+        .find(|line| line.trim_start().starts_with(&format!("{morpheme} ")))
+        .unwrap_or_else(|| panic!("no row for {morpheme} in:\n{stdout}"))
+        .split_once("-> ")
+        .and_then(|(_, row)| row.split_once(" (n="))
+        .expect("rows have an arrow and a count")
+        .0
+        .to_string()
+}
@@ -0,0 +1,368 @@
+//! End-to-end checks that the feature, scaling and statistics flags reach
+//! their modules; the arithmetic itself is unit-tested next to each module.
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use common::*;
+
+#[test]
+fn feature_extractors_name_their_dimensions() {
+    let stdout = run(&["--features", "basic"]);
+    assert!(
+        stdout.contains(
+            "dimensions: basic.word_count, basic.mean_token_length, basic.sensory_ratio, \
+             basic.abstract_ratio, basic.complexity"
+        ),
+        "missing dimension names in:\n{stdout}"
+    );
+
+    let output = erebus(&["--features", "basic,telepathy"]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("unknown feature extractor 'telepathy'")
+    );
+}
+
+#[test]
+fn tfidf_vectors_share_one_vocabulary_without_stopwords() {
+    let dictionary = temp_file(
+        "tfidf.tsv",
+        "kindness\tthe quality of being kind.\nunkind\tnot kind to others.\n",
+    );
+
+    // "kind" is in every definition, so its idf is zero; "the" and "of"
+    // are stopwords and never enter the vocabulary.
+    let (stdout, _) = run_on(&dictionary, &[&["--features", "tfidf"]]);
+    assert!(
+        stdout.contains("dimensions: tfidf.kind, tfidf.others, tfidf.quality"),
+        "unexpected vocabulary in:\n{stdout}"
+    );
+    assert_eq!(row(&stdout, "suffix:ness"), "[0.000, 0.000, 1.000]");
+
+    let (stdout, _) = run_on(
+        &dictionary,
+        &[&["--features", "bow", "--stopwords", "none"]],
+    );
+    assert!(
+        stdout.contains(
+            "dimensions: bow.being, bow.kind, bow.not, bow.of, bow.others, bow.quality, \
+             bow.the, bow.to"
+        ),
+        "unexpected vocabulary in:\n{stdout}"
+    );
+}
+
+#[test]
+fn lsa_reduces_tfidf_rows_to_dense_dimensions() {
+    let dictionary = temp_file(
+        "lsa.tsv",
+        "kindness\tthe quality of being kind.\nunkind\tnot kind to others.\n",
+    );
+    let (stdout, _) = run_on(&dictionary, &[&["--features", "tfidf", "--dims", "5"]]);
+    // Two distinct definitions give a rank-two matrix, so only two of the
+    // five requested dimensions survive and nothing is lost.
+    for line in [
+        "Latent semantic analysis: 2 of 3 features kept as dimensions, 100.0% of variance retained",
+        "dimensions: lsa.1, lsa.2",
+    ] {
+        assert!(stdout.contains(line), "missing '{line}' in:\n{stdout}");
+    }
+}
+
+#[test]
+fn skipgram_training_is_reproducible_from_its_seed() {
+    let run = |seed: &str| {
+        run(&[
+            "--features",
+            "skipgram",
+            "--skipgram-dims",
+            "4",
+            "--seed",
+            seed,
+        ])
+        .lines()
+        .skip_while(|line| !line.starts_with("Derived morpheme embedding matrix"))
+        .map(str::to_string)
+        .collect::<Vec<_>>()
+    };
+
+    let first = run("7");
+    assert!(
+        first
+            .iter()
+            .any(|line| line == "  dimensions: skipgram.1, skipgram.2, skipgram.3, skipgram.4"),
+        "missing dimension names in {first:?}"
+    );
+    assert_eq!(first, run("7"));
+    assert_ne!(first, run("8"));
+}
+
+#[test]
+fn pretrained_vectors_encode_definitions_as_weighted_means() {
+    let dictionary = temp_file(
+        "vectors.tsv",
+        "kindness\tthe quality of being kind.\nunkind\tnot kind to others; harsh.\n",
+    );
+    // fastText/word2vec style, with a header line and a malformed entry.
+    let vec_file = temp_file(
+        "vectors.vec",
+        "4 2\nKind 1 0\nquality 0 1\nothers 0 -1\nharsh 0.5\n",
+    );
+    // GloVe style, no header.
+    let glove_file = temp_file("vectors.txt", "kind 1 0\nquality 0 1\nothers 0 -1\n");
+    let run = |vectors: &PathBuf, extra: &[&str]| {
+        run_on(
+            &dictionary,
+            &[&["--vectors", vectors.to_str().unwrap()], extra],
+        )
+    };
+
+    // `basic` stays in the default set behind the vectors.
+    let (stdout, stderr) = run(&vec_file, &[]);
+    assert!(stderr.contains("vectors.vec:5: expected 2 values, found 1"));
+    assert!(stdout.contains("dimensions: vectors.1, vectors.2, basic.word_count, "));
+    assert!(stdout.contains("suffix:ness            -> [0.500, 0.500, 5.000, "));
+
+    // "kind" is in both definitions, so IDF weighting silences it.
+    let (stdout, _) = run(&glove_file, &["--vectors-idf", "--features", "vectors"]);
+    assert!(
+        stdout.contains("suffix:ness            -> [0.000, 1.000]"),
+        "unexpected rows in:\n{stdout}"
+    );
+
+    // With no word in the file the vectors are zero and `basic` alone tells
+    // the definitions apart.
+    let unrelated = temp_file("unrelated.vec", "zebra 1 1\n");
+    let (stdout, _) = run(&unrelated, &[]);
+    assert!(stdout.contains("Loaded 2-dimensional vectors for 0 of 4 definition words"));
+    assert!(
+        stdout.contains(
+            "suffix:ness            -> [0.000, 0.000, 5.000, 4.200, 0.000, 0.200, 1.200]"
+        ),
+        "unexpected rows in:\n{stdout}"
+    );
+}
+
+#[test]
+fn lexicon_categories_become_dimensions_matched_on_lemmas() {
+    let dictionary = temp_file(
+        "lexicon-dictionary.tsv",
+        "kindness\tglowing, stopped sounds of light.\n",
+    );
+    let weighted = temp_file(
+        "lexicon.tsv",
+        "# word, category, weight\nhappy\temotion\nglow\temotion\t0.5\nloud\tsenses\tvery\n",
+    );
+    // A word list's category is its file name.
+    let directory = temp_file("lexicons", "");
+    fs::remove_file(&directory).expect("failed to clear temp path");
+    fs::create_dir_all(&directory).expect("failed to create temp dir");
+    let word_list = directory.join("senses.txt");
+    fs::write(&word_list, "sound\nlight\nstop\n").expect("failed to write temp file");
+    let lexicons = [
+        "--features",
+        "lexicon",
+        "--lexicon",
+        weighted.to_str().unwrap(),
+        "--lexicon",
+        word_list.to_str().unwrap(),
+    ];
+    let run = |extra: &[&str]| run_on(&dictionary, &[&lexicons, extra]);
+
+    // Five tokens, of which only "light" is listed as written.
+    let (stdout, stderr) = run(&[]);
+    assert!(stderr.contains("lexicon.tsv:4: weight is not a number"));
+    assert!(stdout.contains("dimensions: lexicon.emotion, lexicon.senses"));
+    assert!(
+        stdout.contains("suffix:ness            -> [0.000, 0.200]"),
+        "unexpected rows in:\n{stdout}"
+    );
+
+    // "glowing" is half an emotion word; "stopped", "sounds" and "light"
+    // are sense words once reduced to their lemmas.
+    let (stdout, _) = run(&["--normalize", "lemma"]);
+    assert!(
+        stdout.contains("suffix:ness            -> [0.100, 0.600]"),
+        "unexpected rows in:\n{stdout}"
+    );
+}
+
+#[test]
+fn token_normalizers_merge_inflections_and_spellings() {
+    let dictionary = temp_file(
+        "normalize.tsv",
+        "kindness\tsteals the colours of stages.\nunkind\tto steal a color on a stage; went.\n",
+    );
+    let dimensions = |normalize: &[&str]| {
+        run_on(&dictionary, &[&["--features", "bow"], normalize])
+            .0
+            .lines()
+            .find_map(|line| line.strip_prefix("  dimensions: "))
+            .expect("no dimensions line")
+            .to_string()
+    };
+
+    assert_eq!(
+        dimensions(&[]),
+        "bow.color, bow.colours, bow.stage, bow.stages, bow.steal, bow.steals, bow.went"
+    );
+    assert_eq!(
+        dimensions(&["--normalize", "spelling,lemma"]),
+        "bow.color, bow.go, bow.stage, bow.steal"
+    );
+    assert_eq!(
+        dimensions(&["--normalize", "stem"]),
+        "bow.color, bow.colour, bow.stage, bow.steal, bow.went"
+    );
+}
+
+#[test]
+fn readability_counts_syllables_and_sentences() {
+    let dictionary = temp_file(
+        "readability.tsv",
+        "kindness\tthe quality of being kind. a considerate nature beyond\n",
+    );
+    let cmudict = temp_file(
+        "cmudict.txt",
+        ";;; sample\nBEYOND  B IH0 AA1 N D\nBEYOND(2)  B IY0 AA1 N D\nHMM\n",
+    );
+    let cmudict = cmudict.to_str().expect("temp path is UTF-8");
+    let run = |extra: &[&str]| run_on(&dictionary, &[&["--features", "readability"], extra]);
+
+    let (stdout, _) = run(&[]);
+    assert!(stdout.contains(
+        "dimensions: readability.syllables_per_word, readability.words_per_sentence, \
+         readability.flesch_kincaid_grade, readability.gunning_fog"
+    ));
+    assert_eq!(row(&stdout, "suffix:ness"), "[1.778, 4.500, 7.143, 10.689]");
+
+    // CMUdict knows "beyond" has two syllables, not one.
+    let (stdout, stderr) = run(&["--cmudict", cmudict]);
+    assert!(stderr.contains("cmudict.txt:4: no vowel phoneme"));
+    assert_eq!(row(&stdout, "suffix:ness"), "[1.889, 4.500, 8.454, 10.689]");
+
+    // `basic` shares the same syllabifier, so the file is read and its
+    // malformed line reported once, whichever extractors run.
+    for features in ["basic,readability", "basic"] {
+        let (_, stderr) = run(&["--cmudict", cmudict, "--features", features]);
+        assert_eq!(stderr.matches("no vowel phoneme").count(), 1, "{stderr}");
+    }
+}
+
+#[test]
+fn pos_features_tag_glosses_and_detect_the_headword_class() {
+    let tsv = temp_file(
+        "pos.tsv",
+        "abscond\tto depart abruptly.\nkindly\tin a kind way.\n\
+         kindness\tthe quality of being kind.\nkindred\tof or relating to family.\n",
+    );
+    // A dictionary label wins over the shape of the gloss.
+    let json = temp_file(
+        "pos.jsonl",
+        "{\"word\": \"unkind\", \"pos\": \"adj\", \"senses\": [\"a lack of kindness.\"]}\n",
+    );
+    let (stdout, _) = run_on(
+        &tsv,
+        &[&["--dictionary", json.to_str().unwrap(), "--features", "pos"]],
+    );
+
+    assert!(stdout.contains(
+        "dimensions: pos.noun_ratio, pos.verb_ratio, pos.adjective_ratio, pos.adverb_ratio, \
+         pos.pronoun_ratio, pos.determiner_ratio, pos.preposition_ratio, \
+         pos.conjunction_ratio, pos.headword_noun, pos.headword_verb, \
+         pos.headword_adjective, pos.headword_adverb"
+    ));
+    // to(preposition) depart(verb) abruptly(adverb)
+    assert_eq!(
+        row(&stdout, "prefix:ab"),
+        "[0.000, 0.333, 0.000, 0.333, 0.000, 0.000, 0.333, 0.000, 0.000, 1.000, 0.000, 0.000]"
+    );
+    assert!(row(&stdout, "suffix:ness").ends_with("1.000, 0.000, 0.000, 0.000]"));
+    assert!(row(&stdout, "root:ly").ends_with("0.000, 0.000, 0.000, 1.000]"));
+    assert!(row(&stdout, "root:red").ends_with("0.000, 0.000, 1.000, 0.000]"));
+    assert!(row(&stdout, "root:unki").ends_with("0.000, 0.000, 1.000, 0.000]"));
+}
+
+#[test]
+fn scaling_statistics_are_saved_and_reapplied_to_new_words() {
+    let statistics =
+        std::env::temp_dir().join(format!("erebus-{}-scaling.tsv", std::process::id()));
+    let words = temp_file("scaling-words.txt", "antidisestablishmentarianism\n");
+    let statistics = statistics.to_str().unwrap();
+    // Word counts run from 8 to 13 and the sensory ratio is always 0.
+    let anti = "prefix:anti            -> [1.000, 0.858, 0.000, 1.000, 0.692]";
+
+    let stdout = run(&["--scale", "minmax", "--save-scaler", statistics]);
+    assert!(stdout.contains("Fitted minmax scaling of 5 dimensions on 9 definitions"));
+    assert!(stdout.contains(anti), "unexpected rows in:\n{stdout}");
+    let saved = fs::read_to_string(statistics).expect("statistics were saved");
+    assert!(saved.contains("method\tminmax\nbasic.word_count\t8\t13\n"));
+    assert!(saved.contains("basic.sensory_ratio\t0\t0\n"));
+
+    // Processing one word with the saved statistics gives the same row.
+    let stdout = run(&["--scaler", statistics, words.to_str().unwrap()]);
+    assert!(stdout.contains("Loaded minmax scaling of 5 dimensions"));
+    assert!(stdout.contains(anti), "unexpected rows in:\n{stdout}");
+}
+
+#[test]
+fn morpheme_rows_report_counts_spread_and_drop_rare_morphemes() {
+    let tsv = temp_file(
+        "stats.tsv",
+        "kindly\tin a kind way, gently.\nkindness\tthe quality of being kind.\n\
+         kindred\tof or relating to family.\n",
+    );
+    let run = |extra: &[&str]| {
+        run_on(
+            &tsv,
+            &[&["--features", "lexicon", "--min-count", "2"], extra],
+        )
+        .0
+    };
+
+    // Only "kind" occurs in more than one word; its abstract share is 0,
+    // 0.2 and 0, within 1.96 standard errors of 0.067.
+    let stdout = run(&["--stats"]);
+    assert!(stdout.contains("Dropped 3 of 4 morphemes seen in fewer than 2 words"));
+    assert!(stdout.contains("Derived morpheme embedding matrix (1 morphemes × 2 features)"));
+    assert!(
+        stdout.contains(
+            "  root:kind              -> [0.000, 0.067] (n=3)\n\
+             \x20   variance             -> [0.000, 0.013]\n\
+             \x20   std error            -> [0.000, 0.067]\n\
+             \x20   95% ci low           -> [0.000, -0.064]\n\
+             \x20   95% ci high          -> [0.000, 0.197]\n\
+             \x20   min                  -> [0.000, 0.000]\n\
+             \x20   max                  -> [0.000, 0.200]\n"
+        ),
+        "unexpected rows in:\n{stdout}"
+    );
+
+    // Every one of the three analyses of "kindness" ends in "ness", which
+    // still comes from a single word.
+    let stdout = run(&["--segmenter", "viterbi", "--nbest", "3"]);
+    assert!(
+        stdout.contains("3. p="),
+        "expected three analyses in:\n{stdout}"
+    );
+    assert!(
+        !stdout.contains("suffix:ness "),
+        "unexpected rows in:\n{stdout}"
+    );
+
+    // Reduced rows keep their word counts.
+    let stdout = run(&["--dims", "1"]);
+    assert!(
+        stdout.contains("dimensions: lsa.1"),
+        "unexpected rows in:\n{stdout}"
+    );
+    assert!(
+        stdout
+            .lines()
+            .any(|line| line.starts_with("  root:kind ") && line.ends_with("] (n=3)")),
+        "unexpected rows in:\n{stdout}"
+    );
+}
@@ -0,0 +1,620 @@
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use common::*;
+
+#[test]
+fn builtin_words_keep_their_segmentation() {
+    let actual = segmentations(&[]);
+    assert_eq!(actual.len(), 9);
+    assert_segmentations(
+        &actual,
+        &[
+            ("absquatulate", "prefix(ab) + root(squat) + suffix(ulate)"),
+            (
+                "antidisestablishmentarianism",
+                "prefix(anti) + prefix(dis) + root(establish) + suffix(ment) + suffix(arianism)",
+            ),
+            ("biblioklept", "root(biblio) + root(klept)"),
+            ("cattywampus", "root(cattywampus)"),
+            (
+                "defenestration",
+                "prefix(de) + root(fenestr) + suffix(ation)",
+            ),
+            (
+                "hypermetamorphosis",
+                "prefix(hyper) + root(meta) + root(morph) + suffix(osis)",
+            ),
+            ("kerfuffle", "root(kerfuffle)"),
+            (
+                "sesquipedalian",
+                "prefix(sesqui) + root(pedal) + suffix(ian)",
+            ),
+            (
+                "transmogrification",
+                "prefix(trans) + root(mogr) + suffix(ification)",
+            ),
+        ],
+    );
+}
+
+#[test]
+fn demo_words_with_example_profile() {
+    let actual = segmentations(&[
+        "--morphology",
+        "examples/en.toml",
+        "--dictionary",
+        "examples/demo_definitions.tsv",
+        "examples/demo_words.txt",
+    ]);
+    assert_segmentations(
+        &actual,
+        &[
+            ("luminescence", "root(lumin) + suffix(escence)"),
+            ("resilience", "prefix(re) + root(sil) + suffix(ience)"),
+            ("catalyst", "root(cata) + root(lyst)"),
+            ("melody", "root(mel) + root(ody)"),
+            ("ephemeral", "root(ephemer) + suffix(al)"),
+        ],
+    );
+}
+
+#[test]
+fn malformed_delimited_lines_are_reported_and_skipped() {
+    let csv = temp_file(
+        "malformed.csv",
+        "word,definition\nkindly,\"in a \"\"kind\"\" way, gently.\"\nbroken,\"open\n\
+         kindred\nunkind,not,kind\n",
+    );
+    let tsv = temp_file(
+        "malformed.tsv",
+        "# comment\nkindness\tthe quality of being kind.\nno tab here\nunkind\t \n\tnothing\n",
+    );
+    let (stdout, stderr) = run_with_stderr(&[
+        "--replace-dictionary",
+        "--dictionary",
+        csv.to_str().unwrap(),
+        "--dictionary",
+        tsv.to_str().unwrap(),
+    ]);
+
+    for warning in [
+        "malformed.csv:3: unterminated quoted field",
+        "malformed.csv:4: expected word,definition",
+        "malformed.csv:5: expected 2 fields, found 3",
+        "malformed.tsv:3: expected word<TAB>definition",
+        "malformed.tsv:4: empty definition for 'unkind'",
+        "malformed.tsv:5: empty headword",
+    ] {
+        assert!(
+            stderr.contains(warning),
+            "missing '{warning}' in:\n{stderr}"
+        );
+    }
+    assert!(stdout.contains("(1 senses, 0 gold segmentations) from "));
+    assert!(stdout.contains("malformed.csv (3 malformed records skipped)"));
+    assert!(stdout.contains("malformed.tsv (3 malformed records skipped)"));
+    // The header is skipped and quoted commas and quotes survive.
+    assert!(stdout.contains("- kindly: "));
+    assert!(stdout.contains("  definition: in a \"kind\" way, gently.\n"));
+    assert!(stdout.contains("- kindness: "));
+}
+
+#[test]
+fn json_dictionaries_carry_several_senses_per_headword() {
+    let json = temp_file(
+        "senses.json",
+        "[\n {\"word\": \"kindly\", \"pos\": \"adv\", \
+         \"senses\": [\"in a kind way.\", {\"gloss\": \"gently.\", \"pos\": \"adj\"}]},\n \
+         {\"word\": \"unkind\"},\n 42\n]\n",
+    );
+    let jsonl = temp_file(
+        "senses.jsonl",
+        "{\"word\": \"kindness\", \"definition\": \"the quality of being kind.\"}\n\
+         {\"word\": oops}\n",
+    );
+    let run = |mode: &str| {
+        run_with_stderr(&[
+            "--replace-dictionary",
+            "--dictionary",
+            json.to_str().unwrap(),
+            "--dictionary",
+            jsonl.to_str().unwrap(),
+            "--senses",
+            mode,
+        ])
+    };
+
+    let (stdout, stderr) = run("average");
+    for warning in [
+        "senses.json:3: no definitions for 'unkind'",
+        "senses.json:4: expected an object, found number",
+        "senses.jsonl:2: unexpected character",
+    ] {
+        assert!(
+            stderr.contains(warning),
+            "missing '{warning}' in:\n{stderr}"
+        );
+    }
+    assert!(stdout.contains("senses.json (2 malformed records skipped)"));
+    // A sense's own part of speech wins over the record's.
+    assert!(stdout.contains("  sense 1 (adv): in a kind way.\n  sense 2 (adj): gently.\n"));
+    assert!(stdout.contains("  definition: the quality of being kind.\n"));
+    // "kindly" averages its senses into one row before "kind" averages the
+    // two words; separately, each of the three senses counts once.
+    assert!(
+        stdout.contains("root:kind              -> [3.750, 4.225, 0.000, 0.100, 1.100]"),
+        "unexpected rows in:\n{stdout}"
+    );
+    let (stdout, _) = run("separate");
+    assert!(
+        stdout.contains("root:kind              -> [3.333, 4.233, 0.000, 0.067, 1.067]"),
+        "unexpected rows in:\n{stdout}"
+    );
+}
+
+#[test]
+fn kaikki_import_filters_records_and_reads_gold_templates() {
+    let kaikki = |extra: &[&str]| {
+        let args = [
+            "--replace-dictionary",
+            "--kaikki",
+            "examples/kaikki_sample.jsonl",
+        ];
+        run(&[&args[..], extra].concat())
+    };
+
+    let stdout = kaikki(&[]);
+    assert!(stdout.contains(
+        "Loaded 5 dictionary entries (8 senses, 3 gold segmentations) \
+         from examples/kaikki_sample.jsonl (0 malformed records skipped)"
+    ));
+    assert!(!stdout.contains("catalyseur"));
+    for line in [
+        "  gold: prefix(anti) + prefix(dis) + root(establish) + suffix(ment) + suffix(arian) + suffix(ism)\n",
+        "  gold: root(resilient) + suffix(ence)\n",
+        "  gold: root(window) + root(sill)\n",
+        "  sense 3 (verb): To act as a catalyst for.\n",
+        "  source: Wiktionary (Kaikki.org), CC BY-SA 4.0\n",
+    ] {
+        assert!(stdout.contains(line), "missing '{line}' in:\n{stdout}");
+    }
+
+    let stdout = kaikki(&["--pos", "verb"]);
+    assert!(stdout.contains("Loaded 1 dictionary entries (1 senses, 0 gold segmentations)"));
+    assert!(stdout.contains("  definition (verb): To act as a catalyst for.\n"));
+
+    let stdout = kaikki(&["--lang", "fr"]);
+    assert!(stdout.contains("- catalyseur: "));
+    assert!(!stdout.contains("- catalyst: "));
+}
+
+#[test]
+fn morphology_profiles_extend_or_replace_the_builtin_tables() {
+    let dictionary = temp_file("profile.tsv", "overkindness\tfar too kind.\n");
+    let extending = temp_file(
+        "extending.toml",
+        "extends = \"builtin\"\nprefixes = [\"over\"]\nroots = [\"kind\"]\n",
+    );
+    let standalone = temp_file(
+        "standalone.toml",
+        "name = \"bare\"\nprefixes = [\"over\"]\nroots = [\"kind\"]\n",
+    );
+    let broken = temp_file("broken.toml", "prefixes = [\"over\"\n");
+    let run = |profile: &PathBuf| {
+        run(&[
+            "--morphology",
+            profile.to_str().unwrap(),
+            "--dictionary",
+            dictionary.to_str().unwrap(),
+        ])
+    };
+
+    // The profile's name defaults to its file stem.
+    let stdout = run(&extending);
+    assert!(stdout.contains("Loaded morphology profile 'erebus-"));
+    assert!(stdout.contains("(12 prefixes, 17 suffixes, 22 roots)"));
+    assert!(stdout.contains("- overkindness: prefix(over) + root(kind) + suffix(ness)"));
+    assert!(stdout.contains("- absquatulate: prefix(ab) + root(squat) + suffix(ulate)"));
+
+    let stdout = run(&standalone);
+    assert!(stdout.contains("Loaded morphology profile 'bare' (1 prefixes, 0 suffixes, 1 roots)"));
+    assert!(stdout.contains("- overkindness: prefix(over) + root(kind) + root(ness)"));
+
+    let output = erebus(&["--morphology", broken.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("broken.toml: line 2: expected ',' or ']' in array")
+    );
+}
+
+#[test]
+fn longest_suffix_wins_regardless_of_table_order() {
+    let dictionary = temp_file(
+        "longest.tsv",
+        "catmogrification\ta made-up word.\nfragmentation\tthe process of breaking into pieces.\n",
+    );
+    let actual = segmentations(&["--dictionary", dictionary.to_str().unwrap()]);
+    assert_segmentations(
+        &actual,
+        &[
+            ("catmogrification", "root(cat) + suffix(mogrification)"),
+            ("fragmentation", "root(frag) + suffix(mentation)"),
+        ],
+    );
+}
+
+#[test]
+fn explicit_priority_beats_length() {
+    let dictionary = temp_file(
+        "priority.tsv",
+        "fragmentation\tthe process of breaking into pieces.\n",
+    );
+    let profile = temp_file(
+        "priority.toml",
+        "extends = \"builtin\"\nsuffixes = [{ text = \"ation\", priority = 1 }]\n",
+    );
+    let actual = segmentations(&[
+        "--morphology",
+        profile.to_str().unwrap(),
+        "--dictionary",
+        dictionary.to_str().unwrap(),
+    ]);
+    assert_segmentations(
+        &actual,
+        &[("fragmentation", "root(frag) + suffix(ment) + suffix(ation)")],
+    );
+}
+
+#[test]
+fn viterbi_keeps_unknown_spans_whole() {
+    let actual = segmentations(&[
+        "--segmenter",
+        "viterbi",
+        "--dictionary",
+        "examples/demo_definitions.tsv",
+    ]);
+    assert_segmentations(
+        &actual,
+        &[
+            (
+                "antidisestablishmentarianism",
+                "prefix(anti) + prefix(dis) + root(establish) + suffix(ment) + suffix(arianism)",
+            ),
+            ("cattywampus", "root(cattywampus)"),
+            (
+                "transmogrification",
+                "prefix(trans) + root(mogr) + suffix(ification)",
+            ),
+            ("catalyst", "root(catalyst)"),
+            ("luminescence", "root(luminescence)"),
+        ],
+    );
+}
+
+#[test]
+fn viterbi_nbest_ranks_distinct_splits() {
+    let dictionary = temp_file("nbest.tsv", "hyperkind\ttoo kind by far.\n");
+    let args = [
+        "--replace-dictionary",
+        "--dictionary",
+        dictionary.to_str().unwrap(),
+        "--nbest",
+        "3",
+    ];
+
+    // "hyper" is both a prefix and, unknown, a root; the two labellings of
+    // the same split are one analysis.
+    let stdout = run(&[&args[..], &["--segmenter", "viterbi"]].concat());
+    assert!(
+        stdout.contains(
+            "  n-best:\n\
+             \x20   1. p=0.859 cost=9.000: prefix(hyper) + root(kind)\n\
+             \x20   2. p=0.071 cost=11.500: prefix(hyper) + root(k) + root(ind)\n\
+             \x20   3. p=0.071 cost=11.500: prefix(hyper) + root(ki) + root(nd)\n"
+        ),
+        "unexpected analyses in:\n{stdout}"
+    );
+
+    let output = erebus(&args);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("--nbest needs --segmenter viterbi; 'greedy' yields a single analysis")
+    );
+}
+
+#[test]
+fn morfessor_learns_shared_stems_and_reloads_its_model() {
+    let corpus = affixed_corpus("morfessor-corpus.txt");
+    let model = std::env::temp_dir().join(format!("erebus-{}-morfessor.txt", std::process::id()));
+    let dictionary = temp_file("morfessor.tsv", "playful\tfull of fun.\n");
+
+    let trained = segmentations(&[
+        "--segmenter",
+        "morfessor",
+        "--train-corpus",
+        corpus.to_str().unwrap(),
+        "--save-segmenter-model",
+        model.to_str().unwrap(),
+        "--dictionary",
+        dictionary.to_str().unwrap(),
+    ]);
+    assert_segmentations(&trained, &[("playful", "root(play) + root(ful)")]);
+
+    let reloaded = segmentations(&[
+        "--segmenter",
+        "morfessor",
+        "--segmenter-model",
+        model.to_str().unwrap(),
+        "--dictionary",
+        dictionary.to_str().unwrap(),
+    ]);
+    assert_eq!(trained, reloaded);
+}
+
+#[test]
+fn morfessor_models_keep_their_counts_and_reject_bad_ones() {
+    let model = temp_file(
+        "counted-model.txt",
+        "# erebus morfessor-baseline v1\n2 walk + ing\n3 play + ful\n",
+    );
+    let saved = std::env::temp_dir().join(format!("erebus-{}-resaved.txt", std::process::id()));
+    run(&[
+        "--segmenter",
+        "morfessor",
+        "--segmenter-model",
+        model.to_str().unwrap(),
+        "--save-segmenter-model",
+        saved.to_str().unwrap(),
+    ]);
+    assert_eq!(
+        fs::read_to_string(&saved).expect("model was saved"),
+        "# erebus morfessor-baseline v1\n3 play + ful\n2 walk + ing\n"
+    );
+
+    for count in ["0", "-2"] {
+        let model = temp_file(
+            &format!("bad-model{count}.txt"),
+            &format!("{count} play + ful\n"),
+        );
+        let output = erebus(&[
+            "--segmenter",
+            "morfessor",
+            "--segmenter-model",
+            model.to_str().unwrap(),
+        ]);
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains(":1: malformed analysis"), "{stderr}");
+    }
+}
+
+#[test]
+fn subword_baselines_split_on_frequent_pieces() {
+    let corpus = affixed_corpus("subword-corpus.txt");
+    let dictionary = temp_file(
+        "subword.tsv",
+        "walking\tmoving on foot.\nhelpless\tunable to act.\n",
+    );
+    let args = |segmenter: &'static str| {
+        [
+            "--replace-dictionary",
+            "--dictionary",
+            dictionary.to_str().unwrap(),
+            "--train-corpus",
+            corpus.to_str().unwrap(),
+            "--segmenter",
+            segmenter,
+        ]
+    };
+
+    let expected = [
+        ("walking", "subword(walk) + subword(ing)"),
+        ("helpless", "subword(help) + subword(less)"),
+    ];
+    assert_segmentations(&segmentations(&args("bpe")), &expected);
+    assert_segmentations(&segmentations(&args("unigram")), &expected);
+}
+
+#[test]
+fn spelling_alternations_map_allomorphs_to_known_roots() {
+    let dictionary = temp_file(
+        "alternations.tsv",
+        "happiness\tthe state of being happy.\nstopping\tcoming to a halt.\n\
+         hoping\twishing for something.\ndying\tceasing to live.\n",
+    );
+    let profile = temp_file(
+        "alternations.toml",
+        "extends = \"builtin\"\n\
+         roots = [\"happy\", \"stop\", \"hope\", \"die\"]\n\
+         suffixes = [\"ing\", \"er\", \"ness\"]\n\
+         alternations = [{ name = \"ie-to-y\", surface = \"y\", underlying = \"ie\", before = \"vowel\" }]\n",
+    );
+
+    for segmenter in ["greedy", "viterbi"] {
+        let actual = segmentations(&[
+            "--replace-dictionary",
+            "--morphology",
+            profile.to_str().unwrap(),
+            "--dictionary",
+            dictionary.to_str().unwrap(),
+            "--segmenter",
+            segmenter,
+        ]);
+        assert_segmentations(
+            &actual,
+            &[
+                ("happiness", "root(happi→happy) + suffix(ness)"),
+                ("stopping", "root(stopp→stop) + suffix(ing)"),
+                ("hoping", "root(hop→hope) + suffix(ing)"),
+                ("dying", "root(dy→die) + suffix(ing)"),
+            ],
+        );
+    }
+}
+
+#[test]
+fn derivational_alternations_restore_fy_ent_and_ant_stems() {
+    let dictionary = temp_file(
+        "derivational.tsv",
+        "transmogrification\tthe act of transmogrifying.\n\
+         resilience\tthe ability to recover.\ntolerance\tthe ability to endure.\n",
+    );
+    let profile = temp_file(
+        "derivational.toml",
+        "extends = \"builtin\"\n\
+         roots = [\"mogrify\", \"resilient\", \"tolerant\"]\n\
+         suffixes = [{ text = \"ation\", priority = 1 }, \"ence\", \"ance\"]\n",
+    );
+
+    for segmenter in ["greedy", "viterbi"] {
+        let stdout = run(&[
+            "--replace-dictionary",
+            "--morphology",
+            profile.to_str().unwrap(),
+            "--dictionary",
+            dictionary.to_str().unwrap(),
+            "--segmenter",
+            segmenter,
+        ]);
+        for line in [
+            "- transmogrification: prefix(trans) + root(mogrific→mogrify) + suffix(ation)\n\
+             \x20 spelling: mogrific→mogrify (fy-to-fic)\n",
+            "- resilience: root(resili→resilient) + suffix(ence)\n\
+             \x20 spelling: resili→resilient (ent-to-ence)\n",
+            "- tolerance: root(toler→tolerant) + suffix(ance)\n\
+             \x20 spelling: toler→tolerant (ant-to-ance)\n",
+        ] {
+            assert!(
+                stdout.contains(line),
+                "{segmenter}: missing '{line}' in:\n{stdout}"
+            );
+        }
+    }
+}
+
+#[test]
+fn eval_reports_boundary_scores_and_mistakes() {
+    let gold = temp_file(
+        "gold.tsv",
+        "# word<TAB>morphs\nkerfuffle\tkerfuffle\nfragmentation\tfrag ment ation\n\
+         absquatulate\tab:ab_p squat ulate, absquat ulate\nbroken line without tab\n",
+    );
+    let (stdout, stderr) = run_with_stderr(&["--eval", gold.to_str().unwrap()]);
+
+    assert!(stdout.contains("Loaded 3 gold words"), "{stdout}");
+    assert!(
+        stderr.contains(":5: expected word<TAB>analyses"),
+        "{stderr}"
+    );
+    assert!(
+        stdout.contains(
+            "Boundary precision 1.000, recall 0.750, F1 0.857 \
+             (3 hits, 3 predicted, 4 gold boundaries)"
+        ),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("Exact match: 2 of 3 words (66.7%)"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains(
+            "  fragmentation: predicted frag|mentation, gold frag|ment|ation \
+             (0 spurious, 1 missed)"
+        ),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn compounds_split_into_free_headwords() {
+    let stdout = run(&["--compounds", "--dictionary", "examples/compounds.tsv"]);
+    for tree in [
+        "compound: (window sill)",
+        "compound: (catty wampus)",
+        "compound: ((foot ball) player)",
+        "compound: (sport -s- man)",
+        "compound: (speed -o- meter)",
+    ] {
+        assert!(stdout.contains(tree), "missing '{tree}' in:\n{stdout}");
+    }
+
+    let actual = segmentations(&["--compounds", "--dictionary", "examples/compounds.tsv"]);
+    assert_segmentations(
+        &actual,
+        &[
+            ("sportsman", "root(sport) + linker(s) + root(man)"),
+            ("bookthief", "root(book) + root(thief)"),
+            ("kerfuffle", "root(kerfuffle)"),
+        ],
+    );
+}
+
+#[test]
+fn derivation_trees_attach_affixes_around_dictionary_stems() {
+    let dictionary = temp_file(
+        "derivation.tsv",
+        "establishment\tthe act of establishing a church\n\
+         disestablishment\twithdrawal of state support from a church\n",
+    );
+    let words = temp_file(
+        "derivation-words.txt",
+        "antidisestablishmentarianism\ndisestablishment\n",
+    );
+    let stdout = run(&[
+        "--derivation",
+        "bracketed",
+        "--dictionary",
+        dictionary.to_str().unwrap(),
+        words.to_str().unwrap(),
+    ]);
+    for line in [
+        "derivation: (anti ((dis (establish ment)) arianism))",
+        "derivation: (dis (establish ment))",
+        "stem:establishment ",
+        "stem:disestablishment ",
+    ] {
+        assert!(stdout.contains(line), "missing '{line}' in:\n{stdout}");
+    }
+}
+
+#[test]
+fn source_attributions_are_split_from_definitions() {
+    let tsv = temp_file(
+        "sources.tsv",
+        "kindness\tWiktionary: the quality of being kind.\nunkind\tPhysics: not kind.\n",
+    );
+    let json = temp_file(
+        "sources.jsonl",
+        "{\"word\": \"kindly\", \"source\": \"Field notes\", \"license\": \"CC0\", \
+         \"senses\": [\"Merriam-Webster (abridged): in a kind way.\"]}\n",
+    );
+    let stdout = run(&[
+        "--replace-dictionary",
+        "--dictionary",
+        tsv.to_str().unwrap(),
+        "--dictionary",
+        json.to_str().unwrap(),
+        "--features",
+        "bow",
+    ]);
+
+    assert!(stdout.contains(
+        "  definition: the quality of being kind.\n  source: Wiktionary, CC BY-SA 4.0\n"
+    ));
+    // Only known dictionaries count as attributions.
+    assert!(stdout.contains("  definition: Physics: not kind.\n"));
+    // Explicit JSON fields win over the parsed prefix.
+    assert!(stdout.contains("  definition: in a kind way.\n  source: Field notes, CC0\n"));
+    assert!(stdout.contains(
+        "Definition sources: 1 senses unattributed; 1 senses from Field notes, CC0; \
+         1 senses from Wiktionary, CC BY-SA 4.0"
+    ));
+    // The attribution never reaches the features.
+    assert!(stdout.contains("dimensions: bow.kind, bow.physics, bow.quality, bow.way"));
+}